@@ -0,0 +1,477 @@
+use tlns_tetrio_calcs::{ProfileStats, Ranks};
+
+/// One player out of a command's arguments.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlayerArg {
+    /// A TETR.IO username or user ID.
+    Username(String),
+    /// `$avg<Rank>`, the average player of a rank, or `$avgALL`.
+    Average(Ranks),
+    /// `$med<Rank>`, the median of each stat in a rank, or `$medALL`.
+    Median(Ranks),
+    /// A made up player from `APM PPS VS`, `APM,PPS,VS` or `name:APM,PPS,VS`.
+    Stats {
+        name: Option<String>,
+        apm: f32,
+        pps: f32,
+        vs: f32,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgError {
+    /// Nothing was passed where a player was expected.
+    Empty,
+    /// A number that wasn't followed by the rest of an `APM PPS VS` triple.
+    IncompleteStats(String),
+    /// A stat that isn't a finite positive number, the formulas divide by all three.
+    InvalidNumber(String),
+    UnknownRank(String),
+    UnknownStat(String),
+    /// A `+5apm` style change that couldn't be read.
+    InvalidDelta(String),
+    /// Fewer players than the command needs.
+    TooFew {
+        expected: usize,
+        got: usize,
+    },
+    /// More players than the command takes.
+    TooMany {
+        expected: usize,
+        got: usize,
+    },
+}
+
+impl std::error::Error for ArgError {}
+impl std::fmt::Display for ArgError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => {
+                f.write_str("Expected a username, `$avg<Rank>`, `$med<Rank>` or `APM PPS VS`")
+            }
+            Self::IncompleteStats(s) => write!(
+                f,
+                "`{s}` looks like stats but isn't a full `APM PPS VS` triple"
+            ),
+            Self::InvalidNumber(s) => {
+                write!(
+                    f,
+                    "`{s}` isn't a valid stat, stats must be plain numbers above 0"
+                )
+            }
+            Self::UnknownRank(s) => {
+                write!(f, "`{s}` isn't a rank, ranks look like `X+`, `S-` or `ALL`")
+            }
+            Self::UnknownStat(s) => write!(
+                f,
+                "`{s}` isn't a stat, try something like `APP`, `DS/Piece` or `Cheese Index`"
+            ),
+            Self::InvalidDelta(s) => write!(
+                f,
+                "`{s}` isn't a stat change, write them like `+5apm`, `-0.1pps` or `+10vs`"
+            ),
+            Self::TooFew { expected, got } => {
+                write!(f, "Expected at least {expected} players, got {got}")
+            }
+            Self::TooMany { expected, got } => {
+                write!(f, "Expected at most {expected} player(s), got {got}")
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for PlayerArg {
+    type Err = ArgError;
+
+    /// Parses a single token. Space separated triples need [`parse_many`].
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(rank) = s.strip_prefix("$avg") {
+            return rank
+                .parse()
+                .map(PlayerArg::Average)
+                .map_err(|_| ArgError::UnknownRank(s.to_string()));
+        }
+        if let Some(rank) = s.strip_prefix("$med") {
+            return rank
+                .parse()
+                .map(PlayerArg::Median)
+                .map_err(|_| ArgError::UnknownRank(s.to_string()));
+        }
+        let (name, stats) = match s.split_once(':') {
+            Some((name, stats)) => (Some(name), stats),
+            None => (None, s),
+        };
+        if name.is_some() || stats.contains(',') {
+            let parts: Vec<&str> = stats.split(',').collect();
+            let [apm, pps, vs] = parts[..] else {
+                return Err(ArgError::IncompleteStats(s.to_string()));
+            };
+            return Ok(PlayerArg::Stats {
+                name: name.filter(|n| !n.is_empty()).map(str::to_string),
+                apm: parse_number(apm)?,
+                pps: parse_number(pps)?,
+                vs: parse_number(vs)?,
+            });
+        }
+        if s.is_empty() {
+            return Err(ArgError::Empty);
+        }
+        Ok(PlayerArg::Username(s.to_string()))
+    }
+}
+
+/// Whether `s` is written like a number, so `nan`, `inf` and `infinity` aren't.
+fn is_numeric(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_digit() || c == '.') && s.parse::<f32>().is_ok()
+}
+
+fn parse_number(s: &str) -> Result<f32, ArgError> {
+    Some(s.trim())
+        .filter(|t| is_numeric(t))
+        .and_then(|t| t.parse::<f32>().ok())
+        .filter(|n| n.is_finite() && *n > 0.0)
+        .ok_or_else(|| ArgError::InvalidNumber(s.to_string()))
+}
+
+/// Parses a mix of usernames, `$avg` tokens and stats. Slash commands hand over the whole
+/// argument as one string, so every argument is split on whitespace first.
+pub fn parse_many(args: &[String]) -> Result<Vec<PlayerArg>, ArgError> {
+    let tokens: Vec<&str> = args.iter().flat_map(|a| a.split_whitespace()).collect();
+    if tokens.is_empty() {
+        return Err(ArgError::Empty);
+    }
+    let mut players = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        // a bare number starts an `APM PPS VS` triple, all-digit usernames are read as stats too
+        if is_numeric(tokens[i]) {
+            let Some(triple) = tokens.get(i..i + 3) else {
+                return Err(ArgError::IncompleteStats(tokens[i..].join(" ")));
+            };
+            players.push(PlayerArg::Stats {
+                name: None,
+                apm: parse_number(triple[0])?,
+                pps: parse_number(triple[1])?,
+                vs: parse_number(triple[2])?,
+            });
+            i += 3;
+        } else {
+            players.push(tokens[i].parse()?);
+            i += 1;
+        }
+    }
+    Ok(players)
+}
+
+/// [`parse_many`] for commands that take exactly one player.
+pub fn parse_one(args: &[String]) -> Result<PlayerArg, ArgError> {
+    let mut players = parse_many(args)?;
+    match players.len() {
+        1 => Ok(players.remove(0)),
+        got => Err(ArgError::TooMany { expected: 1, got }),
+    }
+}
+
+/// A change to one base stat, written like `+5apm` or `-0.1pps`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StatDelta {
+    pub base: tlns_tetrio_calcs::BaseStat,
+    pub amount: f64,
+}
+
+impl std::str::FromStr for StatDelta {
+    type Err = ArgError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ArgError::InvalidDelta(s.to_string());
+        let lower = s.to_lowercase();
+        let (number, base) = tlns_tetrio_calcs::BaseStat::ALL
+            .iter()
+            .find_map(|b| Some((lower.strip_suffix(&b.name().to_lowercase())?, *b)))
+            .ok_or_else(err)?;
+        if !number.starts_with(['+', '-']) {
+            return Err(err());
+        }
+        let amount = number
+            .parse::<f64>()
+            .ok()
+            .filter(|n| n.is_finite())
+            .ok_or_else(err)?;
+        Ok(StatDelta { base, amount })
+    }
+}
+
+/// Pulls `+5apm` style changes out of `args`, leaving the player tokens. Usernames can't start
+/// with `+`, and a `-` followed by a digit is read as a change too.
+pub fn split_deltas(args: &[String]) -> Result<(Vec<String>, Vec<StatDelta>), ArgError> {
+    let mut rest = Vec::new();
+    let mut deltas = Vec::new();
+    for token in args.iter().flat_map(|a| a.split_whitespace()) {
+        let mut chars = token.chars();
+        match (chars.next(), chars.next()) {
+            (Some('+'), _) | (Some('-'), Some('0'..='9' | '.')) => deltas.push(token.parse()?),
+            _ => rest.push(token.to_string()),
+        }
+    }
+    Ok((rest, deltas))
+}
+
+/// Where a resolved player's stats came from.
+#[derive(Debug, Clone)]
+pub enum Source {
+    Stats,
+    Average,
+    Median,
+    Leaderboard,
+    Api(tlns_tetrio_calcs::Fetched<()>),
+}
+
+#[derive(Debug, Clone)]
+pub struct ResolvedPlayer {
+    pub stats: ProfileStats,
+    pub source: Source,
+}
+
+impl PlayerArg {
+    /// Looks the player up in the leaderboard cache, falling back to the API for usernames.
+    pub async fn resolve(
+        &self,
+        snapshot: &crate::state::Snapshot,
+        tetrio: &tlns_tetrio_calcs::TetrioClient,
+    ) -> Result<ResolvedPlayer, crate::types::Error> {
+        Ok(match self {
+            PlayerArg::Username(name) => match snapshot.players.get(name) {
+                Some(p) => ResolvedPlayer {
+                    stats: p.clone(),
+                    source: Source::Leaderboard,
+                },
+                None => {
+                    // repeated lookups are served by the client's response cache
+                    let fetched = tetrio.profile(name).await?;
+                    ResolvedPlayer {
+                        stats: fetched.data.clone(),
+                        source: Source::Api(fetched.map(|_| ())),
+                    }
+                }
+            },
+            PlayerArg::Average(rank) => ResolvedPlayer {
+                stats: find_rank_player(&snapshot.avg_players, &format!("$avg{}", rank.symbol()))?,
+                source: Source::Average,
+            },
+            PlayerArg::Median(rank) => ResolvedPlayer {
+                stats: find_rank_player(&snapshot.med_players, &format!("$med{}", rank.symbol()))?,
+                source: Source::Median,
+            },
+            PlayerArg::Stats { name, apm, pps, vs } => ResolvedPlayer {
+                stats: {
+                    let stats = ProfileStats::from_stat(*apm, *pps, *vs);
+                    ProfileStats {
+                        name: Some(
+                            name.clone()
+                                .unwrap_or_else(|| format!("[{apm}, {pps}, {vs}]")),
+                        ),
+                        // made up players have no TR, place them by their estimated TR instead
+                        rank: snapshot.cutoffs.predict(&stats).map(|p| p.rank),
+                        ..stats
+                    }
+                },
+                source: Source::Stats,
+            },
+        })
+    }
+}
+
+fn find_rank_player(
+    players: &[ProfileStats],
+    name: &str,
+) -> Result<ProfileStats, crate::errors::Errors> {
+    players
+        .iter()
+        .find(|p| p.name.as_deref() == Some(name))
+        .cloned()
+        .ok_or(crate::errors::Errors::RankNotFoundError)
+}
+
+/// Resolves every player in order, stopping at the first one that can't be found.
+pub async fn resolve_all(
+    players: &[PlayerArg],
+    snapshot: &crate::state::Snapshot,
+    tetrio: &tlns_tetrio_calcs::TetrioClient,
+) -> Result<Vec<ResolvedPlayer>, crate::types::Error> {
+    let mut resolved = Vec::with_capacity(players.len());
+    for p in players {
+        resolved.push(p.resolve(snapshot, tetrio).await?);
+    }
+    Ok(resolved)
+}
+
+#[cfg(all(test, feature = "test"))]
+mod tests {
+    use super::*;
+
+    fn args(s: &[&str]) -> Vec<String> {
+        s.iter().map(|a| a.to_string()).collect()
+    }
+
+    fn stats(name: Option<&str>, apm: f32, pps: f32, vs: f32) -> PlayerArg {
+        PlayerArg::Stats {
+            name: name.map(str::to_string),
+            apm,
+            pps,
+            vs,
+        }
+    }
+
+    #[test]
+    fn test_rank_tokens() {
+        assert_eq!("$avgX+".parse(), Ok(PlayerArg::Average(Ranks::XPlus)));
+        assert_eq!("$avgALL".parse(), Ok(PlayerArg::Average(Ranks::ALL)));
+        assert_eq!("$meds-".parse(), Ok(PlayerArg::Median(Ranks::SMinus)));
+        assert_eq!(
+            "$avgQ".parse::<PlayerArg>(),
+            Err(ArgError::UnknownRank("$avgQ".to_string()))
+        );
+        assert_eq!(
+            "$med".parse::<PlayerArg>(),
+            Err(ArgError::UnknownRank("$med".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_named_and_comma_stats() {
+        assert_eq!(
+            "bob:60,2.1,130".parse(),
+            Ok(stats(Some("bob"), 60.0, 2.1, 130.0))
+        );
+        assert_eq!("60,2.1,130".parse(), Ok(stats(None, 60.0, 2.1, 130.0)));
+        // an empty name is the same as none
+        assert_eq!(":60,2.1,130".parse(), Ok(stats(None, 60.0, 2.1, 130.0)));
+        assert_eq!(
+            "bob:60,2.1".parse::<PlayerArg>(),
+            Err(ArgError::IncompleteStats("bob:60,2.1".to_string()))
+        );
+        assert_eq!(
+            "bob:60,x,130".parse::<PlayerArg>(),
+            Err(ArgError::InvalidNumber("x".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_bare_numbers_and_usernames() {
+        // slash commands pass everything as one string
+        assert_eq!(
+            parse_many(&args(&["60 2 120 bob"])),
+            Ok(vec![
+                stats(None, 60.0, 2.0, 120.0),
+                PlayerArg::Username("bob".to_string())
+            ])
+        );
+        assert_eq!(
+            parse_many(&args(&["bob", "60", "2", "120"])),
+            Ok(vec![
+                PlayerArg::Username("bob".to_string()),
+                stats(None, 60.0, 2.0, 120.0)
+            ])
+        );
+        // usernames that start with digits but aren't numbers stay usernames
+        assert_eq!(
+            parse_many(&args(&["123abc"])),
+            Ok(vec![PlayerArg::Username("123abc".to_string())])
+        );
+        // all-digit usernames are read as the start of a triple
+        assert_eq!(
+            parse_many(&args(&["123"])),
+            Err(ArgError::IncompleteStats("123".to_string()))
+        );
+        assert_eq!(
+            parse_many(&args(&["60 2"])),
+            Err(ArgError::IncompleteStats("60 2".to_string()))
+        );
+        // float keywords are usernames, not stats
+        assert_eq!(
+            parse_many(&args(&["nan INF Infinity"])),
+            Ok(["nan", "INF", "Infinity"]
+                .map(|u| PlayerArg::Username(u.to_string()))
+                .to_vec())
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(parse_many(&args(&[])), Err(ArgError::Empty));
+        assert_eq!(parse_many(&args(&["  "])), Err(ArgError::Empty));
+        assert_eq!(
+            parse_many(&args(&["60 0 120"])),
+            Err(ArgError::InvalidNumber("0".to_string()))
+        );
+        assert_eq!(
+            parse_many(&args(&["60 -2 120"])),
+            Err(ArgError::InvalidNumber("-2".to_string()))
+        );
+        assert_eq!(
+            parse_many(&args(&["60 2 inf"])),
+            Err(ArgError::InvalidNumber("inf".to_string()))
+        );
+        assert_eq!(
+            parse_many(&args(&["60 2 1e999"])),
+            Err(ArgError::InvalidNumber("1e999".to_string()))
+        );
+        assert_eq!(
+            parse_many(&args(&["bob:60,NaN,120"])),
+            Err(ArgError::InvalidNumber("NaN".to_string()))
+        );
+        assert_eq!(
+            parse_one(&args(&["bob alice"])),
+            Err(ArgError::TooMany {
+                expected: 1,
+                got: 2
+            })
+        );
+        assert_eq!(
+            parse_one(&args(&["$avgS"])),
+            Ok(PlayerArg::Average(Ranks::S))
+        );
+    }
+
+    #[test]
+    fn test_stat_delta() {
+        use tlns_tetrio_calcs::BaseStat;
+        let delta = |base, amount| Ok(StatDelta { base, amount });
+        assert_eq!("+5apm".parse(), delta(BaseStat::Apm, 5.0));
+        assert_eq!("-0.1PPS".parse(), delta(BaseStat::Pps, -0.1));
+        assert_eq!("+10vs".parse(), delta(BaseStat::Vs, 10.0));
+        for bad in ["5apm", "+5", "+apm", "+5tr", "+5 apm", "+infapm"] {
+            assert_eq!(
+                bad.parse::<StatDelta>(),
+                Err(ArgError::InvalidDelta(bad.to_string())),
+                "{bad}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_split_deltas() {
+        use tlns_tetrio_calcs::BaseStat;
+        let (rest, deltas) = split_deltas(&args(&["bob +5apm -0.1pps", "+10vs"])).unwrap();
+        assert_eq!(rest, ["bob"]);
+        assert_eq!(
+            deltas
+                .iter()
+                .map(|d| (d.base, d.amount))
+                .collect::<Vec<_>>(),
+            [
+                (BaseStat::Apm, 5.0),
+                (BaseStat::Pps, -0.1),
+                (BaseStat::Vs, 10.0)
+            ]
+        );
+        // a `-` only starts a change when a number follows, so these stay usernames
+        let (rest, deltas) = split_deltas(&args(&["-bob", "5apm"])).unwrap();
+        assert_eq!(rest, ["-bob", "5apm"]);
+        assert!(deltas.is_empty());
+        assert_eq!(
+            split_deltas(&args(&["bob", "+5xyz"])),
+            Err(ArgError::InvalidDelta("+5xyz".to_string()))
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use tlns_tetrio_calcs::{Metric, Ranks};
+
+/// Discord shows at most this many choices.
+const MAX_CHOICES: usize = 25;
+/// Longest choice Discord accepts, one longer choice makes it reject the whole list.
+const MAX_CHOICE_LEN: usize = 100;
+
+/// Suggests cached usernames and `$avg`/`$med` tokens for the last word of `partial`, so
+/// arguments like `osk vs player1 play` complete the second player without touching the first.
+pub async fn players(ctx: crate::types::Context<'_>, partial: &str) -> Vec<String> {
+    let (head, token) = match partial.rfind(char::is_whitespace) {
+        Some(i) => partial.split_at(i + 1),
+        None => ("", partial),
+    };
+    let snapshot = ctx.data().snapshot.load();
+
+    let mut choices: Vec<String> = Vec::with_capacity(MAX_CHOICES);
+    if !token.is_empty() && !token.starts_with('$') {
+        choices.extend(
+            snapshot
+                .players
+                .search(token, MAX_CHOICES)
+                .into_iter()
+                .filter_map(|p| p.name.clone()),
+        );
+    }
+    choices.extend(rank_tokens(&snapshot, token));
+    with_head(head, choices)
+}
+
+/// Puts the earlier words back in front of each choice, since the choice replaces the whole
+/// argument. Choices that come out too long are dropped, cutting them would change the argument.
+fn with_head(head: &str, choices: Vec<String>) -> Vec<String> {
+    choices
+        .into_iter()
+        .map(|c| format!("{head}{c}"))
+        .filter(|c| c.chars().count() <= MAX_CHOICE_LEN)
+        .take(MAX_CHOICES)
+        .collect()
+}
+
+/// Suggests stat names for commands that sort or pick by stat, prefix matches first.
+pub async fn stats(_ctx: crate::types::Context<'_>, partial: &str) -> Vec<String> {
+    let partial = partial.to_lowercase();
+    let mut names: Vec<&str> = tlns_tetrio_calcs::Stat::ALL
+        .iter()
+        .map(|s| s.name())
+        .filter(|n| n.to_lowercase().contains(&partial))
+        .collect();
+    names.sort_by_key(|n| !n.to_lowercase().starts_with(&partial));
+    names.into_iter().map(str::to_string).collect()
+}
+
+/// `$avg` and `$med` tokens matching `token`, prefix matches first and shortest first within
+/// each group.
+fn rank_tokens(snapshot: &crate::state::Snapshot, token: &str) -> Vec<String> {
+    // before the first crawl lands every rank is offered, `ts` will say which ones aren't ready
+    let mut tokens: Vec<String> = if snapshot.avg_players.is_empty() {
+        Ranks::VARIANTS
+            .iter()
+            .filter(|r| **r != Ranks::Z)
+            .flat_map(|r| [format!("$avg{}", r.symbol()), format!("$med{}", r.symbol())])
+            .collect()
+    } else {
+        snapshot
+            .avg_players
+            .iter()
+            .chain(&snapshot.med_players)
+            .filter_map(|p| p.name.clone())
+            .collect()
+    };
+    let token = token.to_lowercase();
+    tokens.retain(|t| t.to_lowercase().contains(&token));
+    tokens.sort_by_cached_key(|t| (!t.to_lowercase().starts_with(&token), t.len(), t.clone()));
+    tokens
+}
+
+#[cfg(all(test, feature = "test"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_head() {
+        let choices = |names: &[&str]| names.iter().map(|n| n.to_string()).collect();
+        assert_eq!(
+            with_head("osk ", choices(&["player1", "player2"])),
+            ["osk player1", "osk player2"]
+        );
+        let head = "a ".repeat(45);
+        assert_eq!(
+            with_head(&head, choices(&["short", "muchlongername"])),
+            [head.clone() + "short"]
+        );
+        assert_eq!(with_head("", choices(&["x"; 30])).len(), MAX_CHOICES);
+    }
+}
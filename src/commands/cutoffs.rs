@@ -0,0 +1,55 @@
+use prettytable::{format::Alignment, Cell, Row, Table};
+
+/// Shows the TR every rank currently starts at.
+#[poise::command(prefix_command, slash_command)]
+pub async fn cutoffs(ctx: crate::types::Context<'_>) -> Result<(), crate::types::Error> {
+    ctx.defer().await?;
+    let snapshot = ctx.data().snapshot.load_full();
+    if snapshot.cutoffs.is_empty() {
+        return Err("The leaderboard hasn't been crawled yet, try again in a few minutes".into());
+    }
+
+    let mut table = Table::new();
+    table.set_format(*prettytable::format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+    table.set_titles(Row::new(
+        ["Rank", "Top %", "TR", "Players"]
+            .into_iter()
+            .map(Cell::new)
+            .collect(),
+    ));
+    for ((rank, tr), (_, pct)) in snapshot
+        .cutoffs
+        .iter()
+        .zip(tlns_tetrio_calcs::RANK_PERCENTILES)
+    {
+        table.add_row(Row::new(vec![
+            Cell::new(&rank.symbol()),
+            Cell::new_align(&pct.to_string(), Alignment::RIGHT),
+            Cell::new_align(&format!("{tr:.2}"), Alignment::RIGHT),
+            Cell::new_align(
+                &snapshot.players.by_rank(rank).count().to_string(),
+                Alignment::RIGHT,
+            ),
+        ]));
+    }
+
+    let mut embed = poise::serenity_prelude::CreateEmbed::new()
+        .colour(poise::serenity_prelude::Color::from_rgb(0, 153, 255))
+        .title("Rank cutoffs")
+        .description(format!(
+            "TR needed for each rank across {} ranked players\n```\n{table}```",
+            snapshot.cutoffs.players()
+        ))
+        .footer(poise::serenity_prelude::CreateEmbedFooter::new(
+            "From osker's leaderboard cache (refreshed every 5 minutes), data as of",
+        ));
+    if let Some(t) = snapshot
+        .refreshed_at
+        .and_then(|r| poise::serenity_prelude::Timestamp::from_unix_timestamp(r.timestamp()).ok())
+    {
+        embed = embed.timestamp(t);
+    }
+    ctx.send(poise::CreateReply::default().embed(embed).reply(true))
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use tlns_tetrio_calcs::{Metric, Stat};
+
+/// Shows how a stat is calculated for a player, step by step.
+#[poise::command(prefix_command, slash_command)]
+pub async fn explain(
+    ctx: crate::types::Context<'_>,
+    #[description = "Stat to explain, like Opener, Cheese Index or Estimated TR"]
+    #[autocomplete = "crate::commands::autocomplete::stats"]
+    stat: String,
+    #[description = "Username, $avg`Rank`, $med`Rank`, 'APM PPS VS' or 'name:APM,PPS,VS'"]
+    #[autocomplete = "crate::commands::autocomplete::players"]
+    player: Vec<String>,
+) -> Result<(), crate::types::Error> {
+    ctx.defer().await?;
+    let stat: Stat = stat
+        .parse()
+        .map_err(|_| crate::args::ArgError::UnknownStat(stat))?;
+    let snapshot = ctx.data().snapshot.load_full();
+    let player = crate::args::parse_one(&player)?
+        .resolve(&snapshot, &ctx.data().tetrio)
+        .await?
+        .stats;
+    let name = player.name.clone().unwrap_or_default();
+    let trace = tlns_tetrio_calcs::explain(&player, stat)
+        .ok_or_else(|| format!("{name} has no {stat}, it only exists for ranked players"))?;
+
+    let steps = trace
+        .steps
+        .iter()
+        .map(|s| format!("➤{} = {} = **{:.4}**", s.label, s.formula, s.value))
+        .collect::<Vec<String>>()
+        .join("\n");
+    let embed = poise::serenity_prelude::CreateEmbed::new()
+        .colour(poise::serenity_prelude::Color::from_rgb(0, 153, 255))
+        .title(format!("How {name}'s {stat} is calculated"))
+        .description(format!(
+            "{stat} = {}\n\n{steps}\n\n{stat} = **{}**",
+            trace.formula,
+            stat.display(trace.result)
+        ))
+        .footer(poise::serenity_prelude::CreateEmbedFooter::new(
+            "Steps are rounded to 4 decimals, the result is calculated unrounded",
+        ));
+    ctx.send(poise::CreateReply::default().embed(embed).reply(true))
+        .await?;
+    Ok(())
+}
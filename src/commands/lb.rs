@@ -0,0 +1,153 @@
+use poise::serenity_prelude as serenity;
+use tlns_tetrio_calcs::{Metric, ProfileStats, Ranks, Stat};
+
+/// Players shown on each page.
+const PAGE_SIZE: usize = 10;
+/// How long the page buttons keep working after the last press.
+const BUTTON_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(300);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, poise::ChoiceParameter)]
+pub enum Order {
+    #[name = "Highest first"]
+    Descending,
+    #[name = "Lowest first"]
+    Ascending,
+}
+
+/// Ranks cached leaderboard players by any stat.
+#[poise::command(prefix_command, slash_command)]
+pub async fn lb(
+    ctx: crate::types::Context<'_>,
+    #[description = "Stat to sort by, like APP, DS/Piece or Cheese Index"]
+    #[autocomplete = "crate::commands::autocomplete::stats"]
+    stat: String,
+    #[description = "Sort order, highest first by default"] order: Option<Order>,
+    #[description = "Only players of this rank, like S+ or X"] rank: Option<String>,
+    #[description = "Only players with at least this much TR"] min_tr: Option<f64>,
+    #[description = "Only players with at most this much TR"] max_tr: Option<f64>,
+) -> Result<(), crate::types::Error> {
+    ctx.defer().await?;
+    let stat: Stat = stat
+        .parse()
+        .map_err(|_| crate::args::ArgError::UnknownStat(stat))?;
+    let rank = match rank {
+        Some(r) => Some(
+            r.parse::<Ranks>()
+                .map_err(|_| crate::args::ArgError::UnknownRank(r))?,
+        )
+        .filter(|r| *r != Ranks::ALL),
+        None => None,
+    };
+    let order = order.unwrap_or(Order::Descending);
+
+    // the snapshot is kept for the whole session so pages don't shift under a refresh
+    let snapshot = ctx.data().snapshot.load_full();
+    let players: Box<dyn Iterator<Item = &ProfileStats>> = match rank {
+        Some(r) => Box::new(snapshot.players.by_rank(r)),
+        None => Box::new(snapshot.players.iter()),
+    };
+    let mut rows: Vec<(&ProfileStats, f64)> = players
+        .filter(|p| {
+            let tr = p.tr.unwrap_or(0.0);
+            min_tr.is_none_or(|m| tr >= m) && max_tr.is_none_or(|m| tr <= m)
+        })
+        .filter_map(|p| Some((p, stat.of(p).filter(|v| v.is_finite())?)))
+        .collect();
+    if rows.is_empty() {
+        return Err("No cached players match those filters".into());
+    }
+    rows.sort_by(|a, b| match order {
+        Order::Descending => b.1.total_cmp(&a.1),
+        Order::Ascending => a.1.total_cmp(&b.1),
+    });
+
+    let mut title = format!(
+        "{} by {stat}",
+        match order {
+            Order::Descending => "Highest",
+            Order::Ascending => "Lowest",
+        }
+    );
+    if let Some(r) = rank {
+        title += &format!(" in {}", r.symbol());
+    }
+    match (min_tr, max_tr) {
+        (Some(min), Some(max)) => title += &format!(" ({min}-{max} TR)"),
+        (Some(min), None) => title += &format!(" ({min}+ TR)"),
+        (None, Some(max)) => title += &format!(" (up to {max} TR)"),
+        (None, None) => {}
+    }
+    let pages = rows.len().div_ceil(PAGE_SIZE);
+    let build_page = |page: usize| {
+        let description = rows
+            .iter()
+            .enumerate()
+            .skip(page * PAGE_SIZE)
+            .take(PAGE_SIZE)
+            .map(|(i, (p, v))| {
+                format!(
+                    "`#{}` **{}** {} ({}, {} TR)",
+                    i + 1,
+                    p.name.as_deref().unwrap_or_default(),
+                    stat.display(*v),
+                    p.rank.unwrap_or(Ranks::Z).symbol(),
+                    tlns_tetrio_calcs::truncate(p.tr.unwrap_or(0.0), 2)
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+        serenity::CreateEmbed::new()
+            .colour(serenity::Color::from_rgb(0, 153, 255))
+            .title(&title)
+            .description(description)
+            .footer(serenity::CreateEmbedFooter::new(format!(
+                "Page {} of {pages}, {} players",
+                page + 1,
+                rows.len()
+            )))
+    };
+
+    let id_prefix = format!("{}:", ctx.id());
+    let prev_button_id = format!("{id_prefix}prev");
+    let next_button_id = format!("{id_prefix}next");
+    let mut reply = poise::CreateReply::default()
+        .embed(build_page(0))
+        .reply(true);
+    if pages > 1 {
+        reply = reply.components(vec![serenity::CreateActionRow::Buttons(vec![
+            serenity::CreateButton::new(&prev_button_id).emoji('◀'),
+            serenity::CreateButton::new(&next_button_id).emoji('▶'),
+        ])]);
+    }
+    ctx.send(reply).await?;
+    if pages <= 1 {
+        return Ok(());
+    }
+
+    let mut page = 0;
+    while let Some(press) = serenity::collector::ComponentInteractionCollector::new(ctx)
+        .filter({
+            let id_prefix = id_prefix.clone();
+            move |press| press.data.custom_id.starts_with(&id_prefix)
+        })
+        .timeout(BUTTON_TIMEOUT)
+        .await
+    {
+        if press.data.custom_id == next_button_id {
+            page = (page + 1) % pages;
+        } else if press.data.custom_id == prev_button_id {
+            page = page.checked_sub(1).unwrap_or(pages - 1);
+        } else {
+            continue;
+        }
+        press
+            .create_response(
+                ctx.serenity_context(),
+                serenity::CreateInteractionResponse::UpdateMessage(
+                    serenity::CreateInteractionResponseMessage::new().embed(build_page(page)),
+                ),
+            )
+            .await?;
+    }
+    Ok(())
+}
@@ -1,4 +1,11 @@
+pub mod autocomplete;
+pub mod cutoffs;
+pub mod explain;
+pub mod lb;
+pub mod needed;
 pub mod ping;
+pub mod ranks;
 pub mod ts;
 pub mod vs;
 pub mod vst;
+pub mod whatif;
@@ -0,0 +1,115 @@
+use tlns_tetrio_calcs::{Direction, ProfileStats, Ranks};
+
+/// Shows how much APM, PPS or VS a player needs for a TR or rank.
+#[poise::command(prefix_command, slash_command)]
+pub async fn needed(
+    ctx: crate::types::Context<'_>,
+    #[description = "TR to reach, or a rank like S+ to reach its current cutoff"] target: String,
+    #[description = "Username, $avg`Rank`, $med`Rank`, 'APM PPS VS' or 'name:APM,PPS,VS'"]
+    #[autocomplete = "crate::commands::autocomplete::players"]
+    player: Vec<String>,
+) -> Result<(), crate::types::Error> {
+    ctx.defer().await?;
+    let snapshot = ctx.data().snapshot.load_full();
+    let (target_tr, target_name) = match target.parse::<f64>() {
+        Ok(tr) => (tr, format!("{tr} TR")),
+        Err(_) => {
+            let rank: Ranks = target
+                .parse()
+                .map_err(|_| crate::args::ArgError::UnknownRank(target.clone()))?;
+            if snapshot.cutoffs.is_empty() {
+                return Err("Rank cutoffs aren't known until the leaderboard is crawled".into());
+            }
+            let tr = snapshot.cutoffs.get(rank).ok_or_else(|| {
+                format!(
+                    "{} has no TR cutoff to reach, pick a rank from X+ to D or a TR",
+                    rank.symbol()
+                )
+            })?;
+            (
+                tr,
+                format!(
+                    "{} ({} TR)",
+                    rank.symbol(),
+                    tlns_tetrio_calcs::truncate(tr, 2)
+                ),
+            )
+        }
+    };
+    let player = crate::args::parse_one(&player)?
+        .resolve(&snapshot, &ctx.data().tetrio)
+        .await?
+        .stats;
+
+    let lines = [
+        ("APM", Direction::APM, Change::Units),
+        ("PPS", Direction::PPS, Change::Units),
+        ("VS", Direction::VS, Change::Units),
+        (
+            "All three scaled together",
+            Direction::proportional(&player),
+            Change::Percent,
+        ),
+    ]
+    .into_iter()
+    .map(|(label, direction, change)| {
+        match (
+            tlns_tetrio_calcs::solve(&player, direction, target_tr),
+            change,
+        ) {
+            (Some(s), _) if s.step == 0.0 => format!("➤{label}: already there"),
+            (Some(s), Change::Percent) => format!(
+                "➤{label}: **{:+.1}%** ({})",
+                s.step * 100.0,
+                describe(&s.stats)
+            ),
+            (Some(s), Change::Units) => {
+                let precision = if direction == Direction::PPS { 2 } else { 1 };
+                format!(
+                    "➤{label}: **{:+.*}** ({})",
+                    precision,
+                    s.step,
+                    describe(&s.stats)
+                )
+            }
+            (None, _) => format!("➤{label}: out of reach on its own"),
+        }
+    })
+    .collect::<Vec<String>>()
+    .join("\n");
+
+    let embed = poise::serenity_prelude::CreateEmbed::new()
+        .colour(poise::serenity_prelude::Color::from_rgb(0, 153, 255))
+        .title(format!(
+            "What {} needs for {target_name}",
+            player.name.as_deref().unwrap_or_default()
+        ))
+        .description(format!(
+            "Estimated TR now: **{}**\nSmallest change that gets there, any stat not named stays put:\n{lines}",
+            tlns_tetrio_calcs::truncate(player.estimated_tr(), 2)
+        ))
+        .footer(poise::serenity_prelude::CreateEmbedFooter::new(
+            "Based on estimated TR from APM, PPS and VS, not the player's real TR",
+        ));
+    ctx.send(poise::CreateReply::default().embed(embed).reply(true))
+        .await?;
+    Ok(())
+}
+
+/// How a row's step is shown.
+#[derive(Clone, Copy)]
+enum Change {
+    /// Plain APM, PPS or VS.
+    Units,
+    /// A proportional step, where `0.1` is 10% more of each stat.
+    Percent,
+}
+
+fn describe(player: &ProfileStats) -> String {
+    format!(
+        "{} APM, {} PPS, {} VS",
+        tlns_tetrio_calcs::truncate(player.apm as f64, 2),
+        tlns_tetrio_calcs::truncate(player.pps as f64, 2),
+        tlns_tetrio_calcs::truncate(player.vs as f64, 2)
+    )
+}
@@ -24,5 +24,5 @@ pub async fn ping(ctx: crate::types::Context<'_>) -> Result<(), crate::types::Er
     } else {
         return Err("Failed to fetch shard".into());
     }
-    return Ok(());
+    Ok(())
 }
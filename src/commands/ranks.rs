@@ -0,0 +1,77 @@
+use prettytable::{format::Alignment, Cell, Row, Table};
+use tlns_tetrio_calcs::{ProfileStats, Ranks};
+
+/// Shows every rank's averages side by side.
+#[poise::command(prefix_command, slash_command)]
+pub async fn ranks(ctx: crate::types::Context<'_>) -> Result<(), crate::types::Error> {
+    ctx.defer().await?;
+    let snapshot = ctx.data().snapshot.load_full();
+    if snapshot.summaries.is_empty() {
+        return Err("The leaderboard hasn't been crawled yet, try again in a few minutes".into());
+    }
+
+    let mut table = Table::new();
+    table.set_format(*prettytable::format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+    table.set_titles(Row::new(
+        [
+            "Rank", "Players", "APM", "PPS", "VS", "APP", "DS/P", "TR", "Glicko",
+        ]
+        .into_iter()
+        .map(Cell::new)
+        .collect(),
+    ));
+    // top rank first, like the in game rank list
+    for rank in Ranks::VARIANTS
+        .iter()
+        .filter(|r| !matches!(r, Ranks::ALL | Ranks::Z))
+    {
+        let mut cells = vec![Cell::new(&rank.symbol())];
+        match snapshot
+            .summaries
+            .iter()
+            .find(|s| s.rank == *rank && s.count > 0)
+        {
+            Some(s) => {
+                let count = s.count as f64;
+                let avg = ProfileStats::from_stat(
+                    (s.apm / count) as f32,
+                    (s.pps / count) as f32,
+                    (s.vs / count) as f32,
+                );
+                cells.extend(
+                    [
+                        s.count.to_string(),
+                        format!("{:.2}", avg.apm),
+                        format!("{:.2}", avg.pps),
+                        format!("{:.2}", avg.vs),
+                        format!("{:.4}", avg.app()),
+                        format!("{:.4}", avg.ds_pieces()),
+                        format!("{:.0}", s.tr / count),
+                        format!("{:.0}", s.glicko / count),
+                    ]
+                    .iter()
+                    .map(|t| Cell::new_align(t, Alignment::RIGHT)),
+                );
+            }
+            None => cells.extend((0..8).map(|_| Cell::new_align("-", Alignment::RIGHT))),
+        }
+        table.add_row(Row::new(cells));
+    }
+
+    let mut embed = poise::serenity_prelude::CreateEmbed::new()
+        .colour(poise::serenity_prelude::Color::from_rgb(0, 153, 255))
+        .title("Averages of every rank")
+        .description(format!("```\n{table}```"))
+        .footer(poise::serenity_prelude::CreateEmbedFooter::new(
+            "From osker's leaderboard cache (refreshed every 5 minutes), data as of",
+        ));
+    if let Some(t) = snapshot
+        .refreshed_at
+        .and_then(|r| poise::serenity_prelude::Timestamp::from_unix_timestamp(r.timestamp()).ok())
+    {
+        embed = embed.timestamp(t);
+    }
+    ctx.send(poise::CreateReply::default().embed(embed).reply(true))
+        .await?;
+    Ok(())
+}
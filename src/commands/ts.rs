@@ -1,85 +1,309 @@
-use std::str::FromStr;
-
 use poise;
-use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
-use regex;
+use tlns_tetrio_calcs::{Metric, Stat};
 
 const FUNNY_IMAGE: &str = "https://statics.timelessnesses.me/poiuu_drawings/sd.png";
-const DETECT_AVG_PATTERN: &str = r"(?m)\$avg(ALL|[a-zA-Z])(.*)"; // my genius brain made this yay
 pub const ZERO_WIDTH_SPACE: &str = "\u{200b}";
 
 /// Displays stats of a user in a table list.
 #[poise::command(prefix_command, slash_command)]
 pub async fn ts(
     ctx: crate::types::Context<'_>,
-    #[description = "Argument that supports custom 'APM PPS VS' value or username or $avg`Rank or ALL`"]
+    #[description = "Username, $avg`Rank or ALL`, $med`Rank or ALL`, 'APM PPS VS' or 'name:APM,PPS,VS'"]
+    #[autocomplete = "crate::commands::autocomplete::players"]
     args: Vec<String>,
 ) -> Result<(), crate::types::Error> {
     ctx.defer().await?;
-    let player: tlns_tetrio_calcs::ProfileStats;
-    let mut should_add = false;
-    let mut is_avg_rank = false;
-    let mut fetched_from_api = false;
-
-    {
-        let players_list = ctx.data().player_lists.read().await;
-        let average_players = ctx.data().avg_players.read().await;
+    let snapshot = ctx.data().snapshot.load_full();
+    let arg = crate::args::parse_one(&args)?;
 
-        if args.len() == 3 {
-            player = tlns_tetrio_calcs::ProfileStats::from_stat(
-                args[0].parse()?,
-                args[1].parse()?,
-                args[2].parse()?,
-            );
-        } else {
-            let r = regex::Regex::new(DETECT_AVG_PATTERN)?;
-            if let Some(_) = r.captures(&args[0]) {
-                player = if let Some(avg) = average_players
-                    .iter()
-                    .find(|i| *i.name.as_ref().unwrap() == args[0])
-                {
-                    is_avg_rank = true;
-                    avg.clone()
-                } else {
-                    return Err(crate::errors::Errors::RankNotFoundError.into());
-                };
-            } else if let Some(p) = players_list
-                .par_iter()
-                .find_first(|i| i.name.clone().unwrap_or_default() == args[0])
-            {
-                player = p.clone();
-            } else {
-                player = tlns_tetrio_calcs::ProfileStats::from_username(&args[0]).await?;
-                should_add = true;
-                fetched_from_api = true;
+    let player = match arg.resolve(&snapshot, &ctx.data().tetrio).await {
+        Ok(p) => p,
+        Err(e) => match e.downcast_ref::<tlns_tetrio_calcs::Errors>() {
+            Some(tlns_tetrio_calcs::Errors::UserNotFound(name)) => {
+                let suggestions = snapshot.players.search(name, 5);
+                if suggestions.is_empty() {
+                    return Err(e);
+                }
+                ctx.send(
+                    poise::CreateReply::default()
+                        .embed(build_suggestions_embed(name, &suggestions))
+                        .reply(true),
+                )
+                .await?;
+                return Ok(());
             }
-        }
-    }
-    if should_add {
-        ctx.data().player_lists.write().await.push(player.clone());
-    }
-
-    let embed = build_player_embed(
-        &player,
-        match is_avg_rank {
-            true => Some(format!("AVERAGE STATS ON RANK {}", player.rank.unwrap())),
-            false => None,
+            _ => return Err(e),
         },
+    };
+
+    let p = &player.stats;
+    let custom_title = match (&arg, &player.source) {
+        (_, crate::args::Source::Average) => {
+            Some(format!("AVERAGE STATS ON RANK {}", p.rank.unwrap()))
+        }
+        (_, crate::args::Source::Median) => {
+            Some(format!("MEDIAN STATS ON RANK {}", p.rank.unwrap()))
+        }
+        (
+            crate::args::PlayerArg::Stats {
+                name: Some(name), ..
+            },
+            _,
+        ) => Some(format!(
+            "ADVANCED STATS OF {name} [{}, {}, {}]",
+            p.apm, p.pps, p.vs
+        )),
+        _ => None,
+    };
+    let fetched_from_api = match player.source {
+        crate::args::Source::Api(f) => Some(f),
+        _ => None,
+    };
+    let prediction = snapshot.cutoffs.predict(p).filter(|_| !p.is_real);
+    let mut embed = build_player_embed(
+        p,
+        custom_title,
         fetched_from_api,
+        snapshot.refreshed_at,
+        prediction,
     );
+    if let Some(field) =
+        p.tr.filter(|_| p.is_real)
+            .and_then(|tr| build_cutoff_field(&snapshot.cutoffs, tr))
+    {
+        embed = embed.field("Rank distance:", field, false);
+    }
+    let closest = tlns_tetrio_calcs::classify(p, &snapshot.distributions, 3);
+    if !closest.is_empty() {
+        embed = embed.field(
+            "Plays like:",
+            closest
+                .iter()
+                .map(|m| {
+                    format!(
+                        "➤**{}** ({}% similar)",
+                        m.rank.symbol(),
+                        tlns_tetrio_calcs::truncate(m.similarity, 1)
+                    )
+                })
+                .collect::<Vec<String>>()
+                .join("\n"),
+            true,
+        );
+    }
+    let percentiles = snapshot.percentiles(p);
+    if !percentiles.is_empty() {
+        embed = embed.field(
+            "Percentiles:",
+            build_percentile_field(&percentiles, p.rank),
+            false,
+        );
+    }
+    if let crate::args::PlayerArg::Average(rank) | crate::args::PlayerArg::Median(rank) = arg {
+        if let Some(d) = snapshot.distributions.get(&rank) {
+            embed = embed.field(
+                format!("Spread in {}:", rank.symbol()),
+                build_distribution_field(d),
+                false,
+            );
+        }
+    }
     ctx.send(poise::CreateReply::default().embed(embed).reply(true))
         .await?;
     // ctx.say(format!("This request is from ch.tetr.io API: {fetched_from_api}")).await?;
     Ok(())
 }
 
+/// How far `tr` is from the next rank up and from dropping a rank, by the current cutoffs.
+fn build_cutoff_field(cutoffs: &tlns_tetrio_calcs::Cutoffs, tr: f64) -> Option<String> {
+    let rank = cutoffs.rank_for(tr)?;
+    let mut lines = vec![format!(
+        "➤Current cutoffs put this at **{}**",
+        rank.symbol()
+    )];
+    if let Some((up, distance)) = cutoffs.next_up(tr) {
+        lines.push(format!(
+            "➤**{}** in **{}** TR",
+            up.symbol(),
+            tlns_tetrio_calcs::truncate(distance, 2)
+        ));
+    }
+    if let Some((down, distance)) = cutoffs.next_down(tr) {
+        lines.push(format!(
+            "➤**{}** after losing **{}** TR",
+            down.symbol(),
+            tlns_tetrio_calcs::truncate(distance, 2)
+        ));
+    }
+    Some(lines.join("\n"))
+}
+
+/// Each stat's percentile among every cached player, then within the player's rank.
+fn build_percentile_field(
+    percentiles: &[(tlns_tetrio_calcs::Stat, f64, Option<f64>)],
+    rank: Option<tlns_tetrio_calcs::Ranks>,
+) -> String {
+    percentiles
+        .iter()
+        .map(|(stat, global, in_rank)| {
+            let mut line = format!("➤{stat}: **{}**", ordinal(*global));
+            if let (Some(p), Some(r)) = (in_rank, rank) {
+                line += &format!(" ({} in {})", ordinal(*p), r.symbol());
+            }
+            line
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// `72.4` as "72nd".
+fn ordinal(percentile: f64) -> String {
+    let n = percentile.floor() as u32;
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{n}{suffix}")
+}
+
+/// Median ± standard deviation, p10/p25/p75/p90 and the range of a rank's main stats.
+fn build_distribution_field(
+    distributions: &std::collections::HashMap<
+        tlns_tetrio_calcs::Stat,
+        tlns_tetrio_calcs::Distribution,
+    >,
+) -> String {
+    [
+        Stat::Apm,
+        Stat::Pps,
+        Stat::Vs,
+        Stat::App,
+        Stat::DsPiece,
+        Stat::Tr,
+        Stat::Glicko,
+    ]
+    .iter()
+    .filter_map(|stat| {
+        let d = distributions.get(stat).filter(|d| d.count() > 0)?;
+        let n = stat.precision();
+        Some(format!(
+            "➤{stat}: **{:.n$}** ±{:.n$} | p10 {:.n$}, p25 {:.n$}, p75 {:.n$}, p90 {:.n$} | {:.n$} to {:.n$}",
+            d.median(),
+            d.stddev(),
+            d.quantile(0.1),
+            d.quantile(0.25),
+            d.quantile(0.75),
+            d.quantile(0.9),
+            d.min(),
+            d.max(),
+        ))
+    })
+    .collect::<Vec<String>>()
+    .join("\n")
+}
+
+/// "Did you mean" embed listing cached players whose names are close to `name`.
+pub fn build_suggestions_embed(
+    name: &str,
+    suggestions: &[&tlns_tetrio_calcs::ProfileStats],
+) -> poise::serenity_prelude::CreateEmbed {
+    poise::serenity_prelude::CreateEmbed::new()
+        .colour(poise::serenity_prelude::Color::from_rgb(0, 153, 255))
+        .title(format!("User {name} not found"))
+        .description(
+            "Did you mean:\n".to_string()
+                + &suggestions
+                    .iter()
+                    .map(|p| {
+                        format!(
+                            "➤**{}** ({}, {} TR)",
+                            p.name.as_deref().unwrap_or_default(),
+                            p.rank.unwrap_or(tlns_tetrio_calcs::Ranks::Z).symbol(),
+                            tlns_tetrio_calcs::truncate(p.tr.unwrap_or(0.0), 2)
+                        )
+                    })
+                    .collect::<Vec<String>>()
+                    .join("\n"),
+        )
+}
+
+/// Stats with a field each at the top of the embed.
+pub(crate) const MAIN_STATS: &[Stat] = &[
+    Stat::Apm,
+    Stat::Pps,
+    Stat::Vs,
+    Stat::DsPiece,
+    Stat::App,
+    Stat::AppDsPiece,
+];
+pub(crate) const ADVANCED_STATS: &[Stat] = &[
+    Stat::DsSecond,
+    Stat::VsApm,
+    Stat::GarbageEfficiency,
+    Stat::CheeseIndex,
+    Stat::WeightedApp,
+];
+/// Glicko and RD are left out, they share a line.
+pub(crate) const RANKING_STATS: &[Stat] = &[
+    Stat::Area,
+    Stat::Tr,
+    Stat::EstimatedTr,
+    Stat::EstimatedTrAccuracy,
+];
+pub(crate) const PLAYSTYLE_STATS: &[Stat] = &[
+    Stat::Opener,
+    Stat::Plonk,
+    Stat::Stride,
+    Stat::InfiniteDownstack,
+];
+
+/// `value` truncated to `stat`'s precision, without a unit since the fields already name it.
+fn bare_value(stat: Stat, value: f64) -> String {
+    tlns_tetrio_calcs::truncate(value, stat.precision()).to_string()
+}
+
+/// `stat` of `player` for a named field, signed when it's a difference.
+fn stat_value(player: &tlns_tetrio_calcs::ProfileStats, stat: Stat) -> Option<String> {
+    let value = stat.of(player)?;
+    let sign = if stat == Stat::EstimatedTrAccuracy && value > 0.0 {
+        "+"
+    } else {
+        ""
+    };
+    Some(format!("{sign}{}", bare_value(stat, value)))
+}
+
+/// "➤Glicko±RD: **glicko**±rd", empty when the player has neither.
+fn glicko_line(player: &tlns_tetrio_calcs::ProfileStats) -> String {
+    match (Stat::Glicko.of(player), Stat::Rd.of(player)) {
+        (Some(glicko), Some(rd)) => format!(
+            "➤Glicko±RD: **{}**±{}\n",
+            bare_value(Stat::Glicko, glicko),
+            bare_value(Stat::Rd, rd)
+        ),
+        _ => String::new(),
+    }
+}
+
+/// "➤Name: **value**" lines for the stats `player` has.
+fn stat_lines(player: &tlns_tetrio_calcs::ProfileStats, stats: &[Stat]) -> String {
+    stats
+        .iter()
+        .filter_map(|stat| Some(format!("➤{stat}: **{}**\n", stat_value(player, *stat)?)))
+        .collect()
+}
+
 fn build_player_embed(
     player: &tlns_tetrio_calcs::ProfileStats,
     custom_title: Option<String>,
-    is_fetched_from_api: bool,
+    fetched_from_api: Option<tlns_tetrio_calcs::Fetched<()>>,
+    refreshed_at: Option<chrono::DateTime<chrono::Local>>,
+    prediction: Option<tlns_tetrio_calcs::Prediction>,
 ) -> poise::serenity_prelude::CreateEmbed {
-    let sign = if player.accuracy_tr() > 0.0 { "+" } else { "" };
-    poise::serenity_prelude::CreateEmbed::new()
+    let mut embed = poise::serenity_prelude::CreateEmbed::new()
         .colour(poise::serenity_prelude::Color::from_rgb(0, 153, 255))
         .title(match custom_title {
             Some(m) => m,
@@ -102,46 +326,67 @@ fn build_player_embed(
                 .icon_url(FUNNY_IMAGE)
                 .url("https://github.com/timelessnesses/osker"),
         )
-        .description("osker - A sheetBot rewrites in Rust that fetches advanced statistics from ch.tetr.io API")
-        .field("APM", tlns_tetrio_calcs::truncate(player.apm as f64, 2).to_string(), true)
-        .field("PPS", tlns_tetrio_calcs::truncate(player.pps as f64, 2).to_string(), true)
-        .field("VS", tlns_tetrio_calcs::truncate(player.vs as f64, 2).to_string(), true)
-        .field("DS/Piece", tlns_tetrio_calcs::truncate(player.ds_pieces(), 4).to_string(), true)
-        .field("APP", tlns_tetrio_calcs::truncate(player.app(), 4).to_string(), true)
-        .field("APP+DS/Piece", tlns_tetrio_calcs::truncate(player.app_ds_per_pieces(), 4).to_string(), true)
+        .description("osker - A sheetBot rewrites in Rust that fetches advanced statistics from ch.tetr.io API");
+    for stat in MAIN_STATS {
+        embed = embed.field(
+            stat.name(),
+            stat_value(player, *stat).unwrap_or_default(),
+            true,
+        );
+    }
+    embed
         .field(ZERO_WIDTH_SPACE, ZERO_WIDTH_SPACE, true)
-        .field("Rank", player.rank.unwrap_or(tlns_tetrio_calcs::Ranks::Z).to_string(), true)
+        .field(
+            "Rank",
+            player
+                .rank
+                .unwrap_or(tlns_tetrio_calcs::Ranks::Z)
+                .to_string(),
+            true,
+        )
         .field(ZERO_WIDTH_SPACE, ZERO_WIDTH_SPACE, true)
-        .field("Advanced:",
-            "➤DS/Second: **".to_string() + &tlns_tetrio_calcs::truncate(player.ds_seconds(), 4).to_string() + "**\n" +
-            "➤VS/APM: **" + &tlns_tetrio_calcs::truncate(player.vs_apm(), 4).to_string() + "**\n" +
-            "➤Garbage Efficiency: **" + &tlns_tetrio_calcs::truncate(player.garbage_efficiency(), 4).to_string() + "**\n" +
-            "➤Cheese Index: **" + &tlns_tetrio_calcs::truncate(player.cheese_index(), 4).to_string() + "**\n" +
-            "➤Weighted APP: **" + &tlns_tetrio_calcs::truncate(player.weighted_app(), 4).to_string() + "**\n\n" 
-        , true)
-        .field("Ranking:", match player.is_real {
-            true => {
-                "➤Area: **".to_string() + &tlns_tetrio_calcs::truncate(player.area(), 4).to_string() + "**\n" +
-                "➤TR: **" + &tlns_tetrio_calcs::truncate(player.tr.unwrap(), 2).to_string() + "**\n" +
-                "➤Estimated TR: **" + &tlns_tetrio_calcs::truncate(player.estimated_tr(), 2).to_string() + "**\n" +
-                "➤Estimated TR Accuracy: **" + sign + &tlns_tetrio_calcs::truncate(player.accuracy_tr(), 2).to_string() + "**\n" +
-                "➤Glicko±RD: **" + &tlns_tetrio_calcs::truncate(player.glicko.unwrap(), 2).to_string() + "**±" + &tlns_tetrio_calcs::truncate(player.rd.unwrap(), 2).to_string() + "\n\n"
+        .field("Advanced:", stat_lines(player, ADVANCED_STATS) + "\n", true)
+        .field(
+            "Ranking:",
+            match player.is_real {
+                true => stat_lines(player, RANKING_STATS) + &glicko_line(player) + "\n",
+                false => match prediction {
+                    Some(prediction) => format!(
+                        "{}➤{}: **{}**\n➤Estimated Glicko: **{}**\n➤Predicted Rank: **{}**\n\
+                         ➤Leaderboard Position: **#{}**\n\n",
+                        stat_lines(player, &[Stat::Area]),
+                        Stat::EstimatedTr,
+                        bare_value(Stat::EstimatedTr, prediction.estimated_tr),
+                        bare_value(Stat::Glicko, prediction.estimated_glicko),
+                        prediction.rank.symbol(),
+                        prediction.position
+                    ),
+                    None => "This is a dummy user, unable to process TRs".to_string(),
+                },
             },
-            false => {
-                "This is a dummy user, unable to process TRs".to_string()
-            }
-        }, true)
-        .field("Playstyle:", 
-        "➤Opener: **".to_string() + &tlns_tetrio_calcs::truncate(player.opener(), 4).to_string() + "**\n" +
-        "➤Plonk: **" + &tlns_tetrio_calcs::truncate(player.plonk(), 4).to_string() + "**\n" +
-        "➤Stride: **" + &tlns_tetrio_calcs::truncate(player.stride(), 4).to_string() + "**\n" +
-        "➤Infinite Downstack: **" + &tlns_tetrio_calcs::truncate(player.infinite_downstack(), 4).to_string() + "**\n"
-        , true)
-        .field("Want to know more?", "Check the calculation formulas code in https://github.com/timelessnesses/osker/blob/main/tlns-tetrio-calcs/src/lib.rs ! ^w^", true)
-        .timestamp(poise::serenity_prelude::Timestamp::now())
-        .footer(poise::serenity_prelude::CreateEmbedFooter::new(match is_fetched_from_api {
-            true => "This command used ch.tetr.io API",
-            false => "This command used it's own interal cache (that is refreshed every 5 minutes)"
-        }))
+            true,
+        )
+        .field("Playstyle:", stat_lines(player, PLAYSTYLE_STATS), true)
+        .field(
+            "Want to know more?",
+            "Use `explain <stat> <player>` to see how any of these is calculated step by step! ^w^",
+            true,
+        )
+        .timestamp(
+            fetched_from_api
+                .as_ref()
+                .and_then(|f| f.cache)
+                .map(|c| (c.cached_at / 1000) as i64)
+                .or(refreshed_at.map(|r| r.timestamp()))
+                .and_then(|t| poise::serenity_prelude::Timestamp::from_unix_timestamp(t).ok())
+                .unwrap_or_else(poise::serenity_prelude::Timestamp::now),
+        )
+        .footer(poise::serenity_prelude::CreateEmbedFooter::new(
+            match fetched_from_api {
+                Some(f) if f.hit => "From ch.tetr.io (served from osker's cache), data as of",
+                Some(_) => "From ch.tetr.io, data as of",
+                None => "From osker's leaderboard cache (refreshed every 5 minutes), data as of",
+            },
+        ))
 }
 // i love men
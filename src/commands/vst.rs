@@ -1 +1,146 @@
+use prettytable::{format::Alignment, Cell, Row, Table};
+use tlns_tetrio_calcs::{Metric, Stat};
 
+/// Most players whose columns fit in an embed's code block without wrapping, which happens
+/// past about 60 characters.
+const MAX_PLAYERS: usize = 3;
+/// Player names in the header are cut to this many characters to keep their columns narrow.
+const NAME_WIDTH: usize = 10;
+/// Appended to the labels of rows without a better side, which get no winner.
+const NO_WINNER: char = '~';
+
+/// Compares players side by side in a table, like sheetBot's vst.
+#[poise::command(prefix_command, slash_command)]
+pub async fn vst(
+    ctx: crate::types::Context<'_>,
+    #[description = "2 or 3 usernames, $avg`Rank`, 'APM PPS VS' or 'name:APM,PPS,VS', mixed freely"]
+    #[autocomplete = "crate::commands::autocomplete::players"]
+    players: Vec<String>,
+) -> Result<(), crate::types::Error> {
+    ctx.defer().await?;
+    let args = crate::args::parse_many(&players)?;
+    if args.len() < 2 {
+        return Err(crate::args::ArgError::TooFew {
+            expected: 2,
+            got: args.len(),
+        }
+        .into());
+    }
+    if args.len() > MAX_PLAYERS {
+        return Err(crate::args::ArgError::TooMany {
+            expected: MAX_PLAYERS,
+            got: args.len(),
+        }
+        .into());
+    }
+    let snapshot = ctx.data().snapshot.load_full();
+    let resolved = crate::args::resolve_all(&args, &snapshot, &ctx.data().tetrio).await?;
+    let players: Vec<&tlns_tetrio_calcs::ProfileStats> =
+        resolved.iter().map(|r| &r.stats).collect();
+
+    let embed = poise::serenity_prelude::CreateEmbed::new()
+        .colour(poise::serenity_prelude::Color::from_rgb(0, 153, 255))
+        .title(
+            players
+                .iter()
+                .map(|p| p.name.as_deref().unwrap_or_default())
+                .collect::<Vec<&str>>()
+                .join(" vs "),
+        )
+        .description(format!("```\n{}```", build_table(&players)))
+        .footer(poise::serenity_prelude::CreateEmbedFooter::new(
+            "* marks the best of each row, ~ rows have no better side, (±) is the difference \
+             from the first player",
+        ));
+    ctx.send(poise::CreateReply::default().embed(embed).reply(true))
+        .await?;
+    Ok(())
+}
+
+/// One row per stat, one column per player, differences under the values.
+fn build_table(players: &[&tlns_tetrio_calcs::ProfileStats]) -> Table {
+    let mut table = Table::new();
+    table.set_format(*prettytable::format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+    table.set_titles(Row::new(
+        std::iter::once(Cell::new("Stat"))
+            .chain(players.iter().map(|p| {
+                let name = p.name.as_deref().unwrap_or_default();
+                Cell::new(&name.chars().take(NAME_WIDTH).collect::<String>())
+            }))
+            .collect(),
+    ));
+
+    for stat in Stat::ALL {
+        let values: Vec<Option<f64>> = players.iter().map(|p| stat.of(p)).collect();
+        let best = best_of(*stat, &values);
+        let label = match stat.higher_is_better() {
+            Some(_) => stat.label().to_string(),
+            None => format!("{}{NO_WINNER}", stat.label()),
+        };
+        let mut cells = vec![Cell::new(&label)];
+        for (i, value) in values.iter().enumerate() {
+            let text = match value {
+                None => "-".to_string(),
+                Some(v) => {
+                    let mut text = format!("{v:.*}", stat.precision());
+                    if best == Some(*v) {
+                        text.push('*');
+                    }
+                    match values[0] {
+                        Some(first) if i > 0 => {
+                            text += &format!("\n({:+.*})", stat.precision(), v - first)
+                        }
+                        _ => {}
+                    }
+                    text
+                }
+            };
+            cells.push(Cell::new_align(&text, Alignment::RIGHT));
+        }
+        table.add_row(Row::new(cells));
+    }
+    table
+}
+
+/// The winning value of a row, if the stat has a better direction and at least two players have
+/// it.
+fn best_of(stat: impl Metric, values: &[Option<f64>]) -> Option<f64> {
+    let higher = stat.higher_is_better()?;
+    let present: Vec<f64> = values.iter().flatten().copied().collect();
+    if present.len() < 2 {
+        return None;
+    }
+    present.into_iter().reduce(|a, b| match higher {
+        true => a.max(b),
+        false => a.min(b),
+    })
+}
+
+#[cfg(all(test, feature = "test"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_fits() {
+        let players: Vec<tlns_tetrio_calcs::ProfileStats> = (0..MAX_PLAYERS)
+            .map(|i| tlns_tetrio_calcs::ProfileStats {
+                name: Some(format!("averylongusername{i}")),
+                tr: Some(24999.99),
+                glicko: Some(3999.99),
+                rd: Some(60.0),
+                is_real: true,
+                ..tlns_tetrio_calcs::ProfileStats::from_stat(
+                    150.0 + i as f32,
+                    4.0,
+                    350.0 - i as f32 * 200.0,
+                )
+            })
+            .collect();
+        let table = build_table(&players.iter().collect::<Vec<_>>()).to_string();
+        for line in table.lines() {
+            assert!(line.chars().count() <= 60, "{line}");
+        }
+        assert!(table.contains("CI~"));
+        assert!(!table.contains("APM~"));
+    }
+}
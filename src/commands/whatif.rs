@@ -0,0 +1,157 @@
+use tlns_tetrio_calcs::{Metric, ProfileStats, Stat};
+
+use super::ts::{ADVANCED_STATS, MAIN_STATS, PLAYSTYLE_STATS, RANKING_STATS};
+
+/// Metrics whose levers are shown.
+const LEVER_STATS: &[Stat] = &[
+    Stat::Area,
+    Stat::Sr,
+    Stat::Opener,
+    Stat::Plonk,
+    Stat::Stride,
+    Stat::InfiniteDownstack,
+];
+
+/// Stats shown before and after, grouped like the `ts` embed.
+const GROUPS: &[(&str, &[Stat])] = &[
+    ("Stats:", MAIN_STATS),
+    ("Advanced:", ADVANCED_STATS),
+    ("Ranking:", RANKING_STATS),
+    ("Playstyle:", PLAYSTYLE_STATS),
+];
+
+/// Shows a player's stats before and after changes like +5apm, or which change helps most.
+#[poise::command(prefix_command, slash_command)]
+pub async fn whatif(
+    ctx: crate::types::Context<'_>,
+    #[description = "A player (username, $avg`Rank`, 'APM PPS VS'...) then changes like +5apm -0.1pps"]
+    #[autocomplete = "crate::commands::autocomplete::players"]
+    args: Vec<String>,
+) -> Result<(), crate::types::Error> {
+    ctx.defer().await?;
+    let snapshot = ctx.data().snapshot.load_full();
+    let (args, deltas) = crate::args::split_deltas(&args)?;
+    let player = crate::args::parse_one(&args)?
+        .resolve(&snapshot, &ctx.data().tetrio)
+        .await?
+        .stats;
+    let embed = if deltas.is_empty() {
+        build_sensitivity_embed(&player)
+    } else {
+        let mut after = player.clone();
+        for delta in &deltas {
+            after = tlns_tetrio_calcs::apply(&after, delta.base.direction(), delta.amount);
+        }
+        if after.apm <= 0.0 || after.pps <= 0.0 || after.vs <= 0.0 {
+            return Err("Those changes take a stat to zero or below".into());
+        }
+        build_comparison_embed(&player, &after, &deltas, &snapshot.cutoffs)
+    };
+    ctx.send(poise::CreateReply::default().embed(embed).reply(true))
+        .await?;
+    Ok(())
+}
+
+/// Every metric before and after the changes, changed ones in bold with their difference.
+fn build_comparison_embed(
+    before: &ProfileStats,
+    after: &ProfileStats,
+    deltas: &[crate::args::StatDelta],
+    cutoffs: &tlns_tetrio_calcs::Cutoffs,
+) -> poise::serenity_prelude::CreateEmbed {
+    let changes = deltas
+        .iter()
+        .map(|d| format!("{:+}{}", d.amount, d.base))
+        .collect::<Vec<String>>()
+        .join(" ");
+    let mut embed = poise::serenity_prelude::CreateEmbed::new()
+        .colour(poise::serenity_prelude::Color::from_rgb(0, 153, 255))
+        .title(format!(
+            "What if {} went {changes}",
+            before.name.as_deref().unwrap_or_default()
+        ));
+    for (title, stats) in GROUPS {
+        let lines = stats
+            .iter()
+            .filter_map(|stat| {
+                let (b, a) = (stat.of(before)?, stat.of(after)?);
+                let n = stat.precision();
+                Some(if tlns_tetrio_calcs::truncate(a - b, n) == 0.0 {
+                    format!("➤{stat}: {b:.n$}")
+                } else {
+                    format!("➤{stat}: {b:.n$} → **{a:.n$}** ({:+.n$})", a - b)
+                })
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+        embed = embed.field(*title, lines, false);
+    }
+    if let (Some(b), Some(a)) = (cutoffs.predict(before), cutoffs.predict(after)) {
+        let mut lines = vec![];
+        if let Some(rank) = before.rank.filter(|_| before.is_real) {
+            lines.push(format!("➤Current rank: {}", rank.symbol()));
+        }
+        lines.push(format!(
+            "➤Predicted rank: {} → **{}**",
+            b.rank.symbol(),
+            a.rank.symbol()
+        ));
+        lines.push(format!(
+            "➤Leaderboard position: #{} → **#{}**",
+            b.position, a.position
+        ));
+        embed = embed.field("Prediction:", lines.join("\n"), false);
+    }
+    embed.footer(poise::serenity_prelude::CreateEmbedFooter::new(
+        "Predictions use estimated TR, real TR doesn't change with stats",
+    ))
+}
+
+/// Levers ranked by how much estimated TR 1% more of each buys, then how the other metrics
+/// respond to +1 of each.
+fn build_sensitivity_embed(player: &ProfileStats) -> poise::serenity_prelude::CreateEmbed {
+    let tr = tlns_tetrio_calcs::partials(player, ProfileStats::estimated_tr)
+        .iter()
+        .map(|p| {
+            format!(
+                "➤+1 {} ≈ **{:+.2}** TR (+1% ≈ {:+.2} TR)",
+                p.base, p.per_unit, p.per_percent
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    let mut embed = poise::serenity_prelude::CreateEmbed::new()
+        .colour(poise::serenity_prelude::Color::from_rgb(0, 153, 255))
+        .title(format!(
+            "What moves {}'s stats",
+            player.name.as_deref().unwrap_or_default()
+        ))
+        .field(
+            format!(
+                "{} ({:.2}), best lever first:",
+                Stat::EstimatedTr,
+                player.estimated_tr()
+            ),
+            tr,
+            false,
+        );
+    for stat in LEVER_STATS {
+        // every player has these, unlike the league stats
+        let metric = |p: &ProfileStats| stat.of(p).unwrap_or_default();
+        let precision = stat.precision();
+        let mut partials = tlns_tetrio_calcs::partials(player, metric);
+        partials.sort_by_key(|p| p.base as u8);
+        embed = embed.field(
+            format!("{stat} ({:.*}):", precision, metric(player)),
+            partials
+                .iter()
+                .map(|p| format!("➤+1 {}: {:+.*}", p.base, precision, p.per_unit))
+                .collect::<Vec<String>>()
+                .join("\n"),
+            true,
+        );
+    }
+    embed.footer(poise::serenity_prelude::CreateEmbedFooter::new(
+        "Slopes at the current stats, bigger changes won't add up exactly",
+    ))
+}
@@ -0,0 +1,416 @@
+use futures_util::{Stream, TryStreamExt};
+use tlns_tetrio_calcs::{
+    models::Prisecter, Errors, LeaderboardPage, LeaderboardQuery, ProfileStats, TetrioClient,
+};
+
+/// How many pages are crawled between checkpoint saves.
+const SAVE_EVERY: u32 = 10;
+/// Checkpoints that haven't been saved for this long are thrown away, the leaderboard moved on
+/// too much since.
+const MAX_CHECKPOINT_AGE: chrono::TimeDelta = chrono::TimeDelta::hours(1);
+
+/// Partial results of a crawl, saved to disk so a crash or rate limit doesn't lose them.
+///
+/// Only the cursor is rewritten on each save. Players are appended to a file next to it, see
+/// [`players_path`], so saving doesn't get slower as the crawl goes on.
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+struct Checkpoint {
+    started_at: Option<chrono::DateTime<chrono::Local>>,
+    saved_at: Option<chrono::DateTime<chrono::Local>>,
+    cursor: Option<Prisecter>,
+    pages: u32,
+    /// Length of the players file this checkpoint covers. Anything past it was appended by a save
+    /// that didn't get to write the checkpoint.
+    players_len: u64,
+    #[serde(skip)]
+    players: Vec<ProfileStats>,
+    /// How many of `players` are already in the players file.
+    #[serde(skip)]
+    saved_players: usize,
+}
+
+/// Where the players of the checkpoint at `path` are appended, one JSON object per line.
+fn players_path(path: &std::path::Path) -> std::path::PathBuf {
+    path.with_extension("players.jsonl")
+}
+
+/// Crawls the whole TETRA LEAGUE leaderboard, resuming from `checkpoint_path` if a previous crawl
+/// didn't finish. The checkpoint is removed once the crawl completes.
+pub async fn crawl(
+    client: &TetrioClient,
+    checkpoint_path: &std::path::Path,
+) -> Result<Vec<ProfileStats>, Errors> {
+    // the crawl shouldn't hold up anyone running a command
+    let client = client.background();
+    let expected = match client.general_stats().await {
+        Ok(s) => Some(s.rankedcount),
+        Err(e) => {
+            log::warn!("Failed to fetch ranked player count, crawling without an ETA: {e}");
+            None
+        }
+    };
+    crawl_pages(checkpoint_path, expected, |after| {
+        client.leaderboard_pages(LeaderboardQuery {
+            after,
+            ..Default::default()
+        })
+    })
+    .await
+}
+
+/// The checkpointing part of [`crawl`], reading pages from whatever `pages` returns for the
+/// cursor to resume after.
+async fn crawl_pages<S>(
+    checkpoint_path: &std::path::Path,
+    expected: Option<u64>,
+    pages: impl FnOnce(Option<Prisecter>) -> S,
+) -> Result<Vec<ProfileStats>, Errors>
+where
+    S: Stream<Item = Result<LeaderboardPage, Errors>>,
+{
+    let mut checkpoint = load_checkpoint(checkpoint_path).await;
+    match checkpoint.started_at {
+        Some(s) => log::info!(
+            "Resuming crawl started at {s} from page {} ({} players)",
+            checkpoint.pages,
+            checkpoint.players.len()
+        ),
+        None => checkpoint.started_at = Some(chrono::Local::now()),
+    }
+
+    let session_start = std::time::Instant::now();
+    let session_start_len = checkpoint.players.len();
+    let mut pages = std::pin::pin!(pages(checkpoint.cursor));
+    loop {
+        let page = match pages.try_next().await {
+            Ok(Some(p)) => p,
+            Ok(None) => break,
+            Err(e) => {
+                save_checkpoint(checkpoint_path, &mut checkpoint).await;
+                return Err(e);
+            }
+        };
+        checkpoint.players.extend(page.entries);
+        checkpoint.cursor = page.cursor;
+        checkpoint.pages += 1;
+
+        let done = checkpoint.players.len();
+        let rate = (done - session_start_len) as f64 / session_start.elapsed().as_secs_f64();
+        let eta = expected
+            .filter(|_| rate.is_finite() && rate > 0.0)
+            .map(|e| {
+                std::time::Duration::from_secs_f64(e.saturating_sub(done as u64) as f64 / rate)
+            });
+        log::info!(
+            "Crawled page {}: {} of {} players, ETA {}",
+            checkpoint.pages,
+            done,
+            expected.map_or("?".to_string(), |e| e.to_string()),
+            eta.map_or("unknown".to_string(), |e| format!("{}s", e.as_secs()))
+        );
+        if checkpoint.pages % SAVE_EVERY == 0 {
+            save_checkpoint(checkpoint_path, &mut checkpoint).await;
+        }
+    }
+
+    remove_checkpoint(checkpoint_path).await;
+    let players = dedup_keep_last(checkpoint.players);
+    log::info!(
+        "Crawl finished with {} players in {} pages",
+        players.len(),
+        checkpoint.pages
+    );
+    Ok(players)
+}
+
+/// A resumed crawl can see players again if they moved across the saved cursor. The later copy
+/// is the fresher one, the earlier came from the checkpoint.
+fn dedup_keep_last(players: Vec<ProfileStats>) -> Vec<ProfileStats> {
+    let mut seen = std::collections::HashSet::new();
+    let mut players: Vec<ProfileStats> = players
+        .into_iter()
+        .rev()
+        .filter(|p| seen.insert(p.id.clone().or_else(|| p.name.clone())))
+        .collect();
+    players.reverse();
+    players
+}
+
+/// Loads the checkpoint at `path` with its players, or starts over when there's no usable one.
+async fn load_checkpoint(path: &std::path::Path) -> Checkpoint {
+    let bytes = match tokio::fs::read(path).await {
+        Ok(b) => b,
+        Err(_) => return fresh_checkpoint(path).await,
+    };
+    let mut checkpoint = match serde_json::from_slice::<Checkpoint>(&bytes) {
+        Ok(c)
+            if c.saved_at
+                .is_some_and(|s| chrono::Local::now() - s < MAX_CHECKPOINT_AGE) =>
+        {
+            c
+        }
+        Ok(_) => {
+            log::info!("Ignoring stale crawl checkpoint");
+            return fresh_checkpoint(path).await;
+        }
+        Err(e) => {
+            log::warn!("Ignoring unreadable crawl checkpoint: {e}");
+            return fresh_checkpoint(path).await;
+        }
+    };
+    match load_players(&players_path(path), checkpoint.players_len).await {
+        Ok(players) => {
+            checkpoint.saved_players = players.len();
+            checkpoint.players = players;
+            checkpoint
+        }
+        Err(e) => {
+            log::warn!("Ignoring crawl checkpoint with unreadable players: {e}");
+            fresh_checkpoint(path).await
+        }
+    }
+}
+
+/// Reads the first `len` bytes of the players file, cutting off anything a crashed save appended
+/// past them so later saves append right after.
+async fn load_players(path: &std::path::Path, len: u64) -> std::io::Result<Vec<ProfileStats>> {
+    let file = tokio::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+        .await?;
+    if file.metadata().await?.len() < len {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            "players file is shorter than the checkpoint says",
+        ));
+    }
+    file.set_len(len).await?;
+    let bytes = tokio::fs::read(path).await?;
+    bytes
+        .split(|b| *b == b'\n')
+        .filter(|line| !line.is_empty())
+        .map(|line| serde_json::from_slice(line).map_err(std::io::Error::from))
+        .collect()
+}
+
+/// An empty checkpoint, with the players of any previous one removed so they aren't appended to.
+async fn fresh_checkpoint(path: &std::path::Path) -> Checkpoint {
+    remove_file(&players_path(path)).await;
+    Checkpoint::default()
+}
+
+/// Appends the players crawled since the last save, then records how far the crawl got.
+async fn save_checkpoint(path: &std::path::Path, checkpoint: &mut Checkpoint) {
+    let mut lines = Vec::new();
+    for player in &checkpoint.players[checkpoint.saved_players..] {
+        if let Err(e) = serde_json::to_writer(&mut lines, player) {
+            log::error!("Failed to serialize crawled player: {e}");
+            return;
+        }
+        lines.push(b'\n');
+    }
+    let appended = async {
+        let mut file = tokio::fs::OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(players_path(path))
+            .await?;
+        tokio::io::AsyncWriteExt::write_all(&mut file, &lines).await?;
+        file.sync_data().await
+    };
+    if let Err(e) = appended.await {
+        log::error!("Failed to save crawled players: {e}");
+        return;
+    }
+    checkpoint.players_len += lines.len() as u64;
+    checkpoint.saved_players = checkpoint.players.len();
+    checkpoint.saved_at = Some(chrono::Local::now());
+
+    let bytes = match serde_json::to_vec(checkpoint) {
+        Ok(b) => b,
+        Err(e) => {
+            log::error!("Failed to serialize crawl checkpoint: {e}");
+            return;
+        }
+    };
+    // write then rename, so a crash mid-write doesn't leave a corrupt checkpoint behind
+    let tmp = path.with_extension("tmp");
+    let result = match tokio::fs::write(&tmp, bytes).await {
+        Ok(()) => tokio::fs::rename(&tmp, path).await,
+        Err(e) => Err(e),
+    };
+    match result {
+        Ok(()) => log::debug!("Saved crawl checkpoint at page {}", checkpoint.pages),
+        Err(e) => log::error!("Failed to save crawl checkpoint: {e}"),
+    }
+}
+
+async fn remove_checkpoint(path: &std::path::Path) {
+    remove_file(path).await;
+    remove_file(&players_path(path)).await;
+}
+
+async fn remove_file(path: &std::path::Path) {
+    if let Err(e) = tokio::fs::remove_file(path).await {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            log::warn!("Failed to remove {}: {e}", path.display());
+        }
+    }
+}
+
+#[cfg(all(test, feature = "test"))]
+mod tests {
+    use super::*;
+
+    /// A fresh directory under the system temp dir for one test.
+    fn temp_dir(test: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("osker-{}-{test}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn player(i: u32, tr: f64) -> ProfileStats {
+        ProfileStats {
+            name: Some(format!("player{i}")),
+            id: Some(format!("id{i}")),
+            tr: Some(tr),
+            is_real: true,
+            ..ProfileStats::from_stat(60.0, 2.0, 120.0)
+        }
+    }
+
+    fn cursor(pri: f64) -> Prisecter {
+        Prisecter {
+            pri,
+            sec: 0.0,
+            ter: 0.0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_round_trip() {
+        let path = temp_dir("round-trip").join("crawl.json");
+        let mut checkpoint = Checkpoint {
+            started_at: Some(chrono::Local::now()),
+            cursor: Some(cursor(123.0)),
+            pages: 4,
+            players: vec![player(0, 1000.0), player(1, 900.0)],
+            ..Default::default()
+        };
+        save_checkpoint(&path, &mut checkpoint).await;
+        // written to a tmp file first, which is renamed over the checkpoint
+        assert!(path.exists());
+        assert!(!path.with_extension("tmp").exists());
+
+        // later saves only append the new players
+        checkpoint.players.push(player(2, 800.0));
+        checkpoint.pages = 5;
+        let first_len = checkpoint.players_len;
+        save_checkpoint(&path, &mut checkpoint).await;
+        let file_len = std::fs::metadata(players_path(&path)).unwrap().len();
+        assert_eq!(file_len, checkpoint.players_len);
+        assert!(file_len < first_len * 2);
+
+        let loaded = load_checkpoint(&path).await;
+        assert_eq!(loaded.started_at, checkpoint.started_at);
+        assert_eq!(loaded.cursor, checkpoint.cursor);
+        assert_eq!(loaded.pages, 5);
+        assert_eq!(loaded.players.len(), 3);
+        assert_eq!(loaded.saved_players, 3);
+    }
+
+    #[tokio::test]
+    async fn test_crashed_save_is_cut_off() {
+        let path = temp_dir("crashed-save").join("crawl.json");
+        let mut checkpoint = Checkpoint {
+            players: vec![player(0, 1000.0)],
+            ..Default::default()
+        };
+        save_checkpoint(&path, &mut checkpoint).await;
+        // a save that appended players but crashed before writing the checkpoint
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(players_path(&path))
+            .unwrap();
+        std::io::Write::write_all(&mut file, b"{\"half a player").unwrap();
+
+        let mut loaded = load_checkpoint(&path).await;
+        assert_eq!(loaded.players.len(), 1);
+        loaded.players.push(player(1, 900.0));
+        save_checkpoint(&path, &mut loaded).await;
+        assert_eq!(load_checkpoint(&path).await.players.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_stale_and_unreadable_checkpoints() {
+        let dir = temp_dir("stale");
+        let stale = dir.join("stale.json");
+        let mut checkpoint = Checkpoint {
+            pages: 4,
+            players: vec![player(0, 1000.0)],
+            ..Default::default()
+        };
+        save_checkpoint(&stale, &mut checkpoint).await;
+        checkpoint.saved_at = Some(chrono::Local::now() - MAX_CHECKPOINT_AGE * 2);
+        std::fs::write(&stale, serde_json::to_vec(&checkpoint).unwrap()).unwrap();
+        let loaded = load_checkpoint(&stale).await;
+        assert_eq!(loaded.pages, 0);
+        assert!(loaded.players.is_empty());
+        assert!(!players_path(&stale).exists());
+
+        // a long crawl that's still saving is kept however long ago it started
+        let long = dir.join("long.json");
+        let mut checkpoint = Checkpoint {
+            started_at: Some(chrono::Local::now() - MAX_CHECKPOINT_AGE * 2),
+            pages: 4,
+            ..Default::default()
+        };
+        save_checkpoint(&long, &mut checkpoint).await;
+        assert_eq!(load_checkpoint(&long).await.pages, 4);
+
+        let garbage = dir.join("garbage.json");
+        std::fs::write(&garbage, "not json").unwrap();
+        assert_eq!(load_checkpoint(&garbage).await.started_at, None);
+        assert_eq!(load_checkpoint(&dir.join("missing.json")).await.pages, 0);
+    }
+
+    #[tokio::test]
+    async fn test_resume_from_cursor() {
+        let path = temp_dir("resume").join("crawl.json");
+        save_checkpoint(
+            &path,
+            &mut Checkpoint {
+                started_at: Some(chrono::Local::now()),
+                cursor: Some(cursor(900.0)),
+                pages: 1,
+                players: vec![player(0, 1000.0), player(1, 900.0)],
+                ..Default::default()
+            },
+        )
+        .await;
+
+        let players = crawl_pages(&path, None, |after| {
+            assert_eq!(after, Some(cursor(900.0)));
+            // player1 moved across the cursor and shows up again with newer stats
+            futures_util::stream::iter([Ok(LeaderboardPage {
+                number: 1,
+                entries: vec![player(1, 950.0), player(2, 800.0)],
+                cursor: Some(cursor(800.0)),
+                last: true,
+            })])
+        })
+        .await
+        .unwrap();
+
+        let names: Vec<&str> = players.iter().filter_map(|p| p.name.as_deref()).collect();
+        assert_eq!(names, ["player0", "player1", "player2"]);
+        assert_eq!(players[1].tr, Some(950.0));
+        // a finished crawl cleans up after itself
+        assert!(!path.exists());
+        assert!(!players_path(&path).exists());
+    }
+}
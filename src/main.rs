@@ -1,14 +1,15 @@
-use std::str::FromStr;
-
 use rayon::{
     self,
     iter::{IntoParallelRefIterator, ParallelExtend, ParallelIterator},
 };
-use tlns_tetrio_calcs::{ProfileStats, Ranks};
+use tlns_tetrio_calcs::{ProfileStats, Ranks, Stat};
 
+mod args;
 mod commands;
+mod crawler;
 // mod db;
 mod state;
+mod store;
 mod types;
 
 mod errors;
@@ -58,179 +59,177 @@ fn setup_logging() {
         .unwrap();
 }
 
-async fn fetch_players_data() -> Vec<ProfileStats> {
-    let session_id = "lalalalalalalala";
-    const LIMIT: u64 = 100;
-    let mut prisecter: Option<String> = None;
-    let client = reqwest::ClientBuilder::new()
-        .user_agent("tlns-tetrio-calcs")
-        .build()
-        .unwrap();
-    let mut should_break = false;
-    let mut output = Vec::new();
-    let mut count_stream = 0;
-    while !should_break && count_stream == 50 { // im trying to be kind osk
-        count_stream += 1;
-        log::debug!("Request number {count_stream}");
-        let mut res = client
-            .get(tlns_tetrio_calcs::API.to_string() + "users/by/league")
-            .query(&[("limit", LIMIT.to_string())]);
-        if let Some(a) = &prisecter {
-            res = res.query(&[("after", &a.as_str())]);
-            log::info!("used prisecter")
-        }
-        res = res.header("X-Session-ID", session_id);
-        log::info!("used session id");
-        let response = res
-            .send()
-            .await
-            .expect("Failed to request players data")
-            .error_for_status()
-            .expect("Probably Ratelimited?");
-        log::info!("{:#?}", response);
-        let response = response.json::<serde_json::Value>().await.unwrap();
-        if !response["success"].as_bool().unwrap() {
-            panic!("Failed to fetch data")
-        }
-        should_break = response["data"]["entries"].as_array().unwrap().len() != 100;
-        for data in response["data"]["entries"].as_array().unwrap() {
-            output.push(ProfileStats {
-                apm: data["league"]["apm"].as_f64().unwrap() as f32,
-                pps: data["league"]["pps"].as_f64().unwrap() as f32,
-                vs: data["league"]["vs"].as_f64().unwrap() as f32,
-                rank: Ranks::from_str(data["league"]["rank"].as_str().unwrap()).ok(),
-                tr: data["league"]["tr"].as_f64(),
-                name: Some(data["username"].as_str().unwrap().to_string()),
-                pfp: Some(
-                    "https://tetr.io/user-content/avatars/".to_string()
-                        + data["_id"].as_str().unwrap()
-                        + ".jpg",
-                ),
-                glicko: data["league"]["glicko"].as_f64(),
-                rd: data["league"]["rd"].as_f64(),
-                is_real: true,
-            });
-        }
-        let p = response["data"]["entries"]
-            .as_array()
-            .unwrap()
-            .last()
-            .unwrap()["p"]
-            .clone();
-        prisecter = Some(format!(
-            "{}:{}:{}",
-            p["pri"].as_f64().unwrap(),
-            p["sec"].as_f64().unwrap(),
-            p["ter"].as_f64().unwrap()
-        ));
+fn build_tetrio_client() -> tlns_tetrio_calcs::TetrioClient {
+    let mut builder = tlns_tetrio_calcs::TetrioClient::builder().user_agent("osker");
+    if let Ok(url) = std::env::var("OSKER_TETRIO_API") {
+        builder = builder.base_url(url);
+    }
+    // ch.tetr.io asks for a stable session ID so paginated leaderboard requests stay consistent
+    builder = builder.session_id(
+        std::env::var("OSKER_SESSION_ID")
+            .unwrap_or_else(|_| format!("osker-{}", chrono::Local::now().timestamp_millis())),
+    );
+    if let Ok(proxy) = std::env::var("OSKER_PROXY") {
+        builder = builder.proxy(proxy);
     }
-    println!("{:#?}", output);
-    output
+    builder.build().expect("Failed to build ch.tetr.io client")
 }
 
+/// Crawls the leaderboard and computes the rank averages off to the side, then swaps both in at
+/// once so readers never wait on a refresh or see data from different crawls.
 async fn initialize_data(
-    p: &std::sync::Arc<tokio::sync::RwLock<Vec<tlns_tetrio_calcs::ProfileStats>>>,
-    a: &std::sync::Arc<tokio::sync::RwLock<Vec<tlns_tetrio_calcs::ProfileStats>>>,
+    client: &tlns_tetrio_calcs::TetrioClient,
+    snapshot: &arc_swap::ArcSwap<state::Snapshot>,
 ) {
     log::info!("Reinitializing data");
-    let mut locked = p.write().await;
-    let players = fetch_players_data().await;
+    let checkpoint = std::env::var("OSKER_CRAWL_CHECKPOINT")
+        .unwrap_or_else(|_| "crawl_checkpoint.json".to_string());
+    let players = match crawler::crawl(client, std::path::Path::new(&checkpoint)).await {
+        Ok(p) => p,
+        Err(e) => {
+            log::error!("Crawl stopped, keeping the old data until it resumes: {e}");
+            return;
+        }
+    };
     log::info!("Got new data from API");
-    locked.clear();
-
-    let stuffs: dashmap::DashMap<tlns_tetrio_calcs::Ranks, PlayerSummarization> =
-        dashmap::DashMap::new();
-    let count: dashmap::DashMap<tlns_tetrio_calcs::Ranks, u128> = dashmap::DashMap::new();
-    players.par_iter().for_each(|d| {
-        stuffs
-            .entry(d.rank.unwrap())
-            .and_modify(|e| {
-                e.count += 1;
-                e.apm += d.apm as f64;
-                e.pps += d.pps as f64;
-                e.vs += d.vs as f64;
-                e.tr += d.tr.unwrap_or(0.0);
-                e.glicko += d.glicko.unwrap_or(0.0);
-                e.rd += d.rd.unwrap_or(0.0);
-            })
-            .or_insert_with(|| PlayerSummarization {
-                apm: d.apm as f64,
-                pps: d.pps as f64,
-                vs: d.vs as f64,
-                rank: d.rank.unwrap(),
-                count: 1,
-                tr: d.tr.unwrap_or(0.0),
-                glicko: d.glicko.unwrap_or(0.0),
-                rd: d.rd.unwrap_or(0.0),
-            });
-        count
-            .entry(d.rank.unwrap())
-            .and_modify(|i| *i += 1u128)
-            .or_insert(1u128);
-    });
-    *locked = players;
 
-    let mut locked2 = a.write().await;
-    locked2.clear();
-    locked2.par_extend(stuffs.par_iter().map(|v| {
-        let rank = v
-            .key()
-            .to_string()
-            .to_uppercase()
-            .replace("PLUS", "+")
-            .replace("MINUS", "-");
+    let players = store::PlayerStore::new(players);
+    let stuffs: Vec<PlayerSummarization> = players
+        .ranks()
+        .map(|rank| {
+            players.by_rank(rank).fold(
+                PlayerSummarization {
+                    apm: 0.0,
+                    pps: 0.0,
+                    vs: 0.0,
+                    rank,
+                    count: 0,
+                    tr: 0.0,
+                    glicko: 0.0,
+                    rd: 0.0,
+                },
+                |mut e, d| {
+                    e.count += 1;
+                    e.apm += d.apm as f64;
+                    e.pps += d.pps as f64;
+                    e.vs += d.vs as f64;
+                    e.tr += d.tr.unwrap_or(0.0);
+                    e.glicko += d.glicko.unwrap_or(0.0);
+                    e.rd += d.rd.unwrap_or(0.0);
+                    e
+                },
+            )
+        })
+        .collect();
 
-        ProfileStats {
-            apm: (v.value().apm / v.value().count as f64) as f32,
-            pps: (v.value().pps / v.value().count as f64) as f32,
-            vs: (v.value().vs / v.value().count as f64) as f32,
-            rank: Some(v.value().rank),
-            tr: Some(v.value().tr / v.value().count as f64),
-            name: Some(format!("$avg{}", rank)),
-            pfp: None,
-            glicko: Some(v.value().glicko / v.value().count as f64),
-            rd: Some(v.value().rd / v.value().count as f64),
-            is_real: false,
-        }
+    let mut avg_players = Vec::new();
+    avg_players.par_extend(stuffs.par_iter().map(|v| ProfileStats {
+        apm: (v.apm / v.count as f64) as f32,
+        pps: (v.pps / v.count as f64) as f32,
+        vs: (v.vs / v.count as f64) as f32,
+        rank: Some(v.rank),
+        tr: Some(v.tr / v.count as f64),
+        name: Some(format!("$avg{}", v.rank.symbol())),
+        id: None,
+        pfp: None,
+        glicko: Some(v.glicko / v.count as f64),
+        rd: Some(v.rd / v.count as f64),
+        is_real: false,
     }));
-    let mut apm = 0.0;
-    let mut pps = 0.0;
-    let mut vs = 0.0;
-    let mut glicko = 0.0;
-    let mut tr = 0.0;
-    let mut rd = 0.0;
-
-    for x in stuffs.iter() {
-        apm += x.apm;
-        pps += x.pps;
-        vs += x.vs;
-        glicko += x.glicko;
-        tr += x.tr;
-        rd += x.rd;
-    }
-    let c = count.len() as f64;
-    let mut max_rank = Ranks::Z;
-    let mut max_count = 0;
-    for rank in count {
-        if max_count < rank.1 {
-            max_rank = rank.0;
-            max_count = rank.1;
-        }
-    }
+    // weighted by player count, so a handful of X+ players don't count as much as all of D
+    let total = stuffs.iter().fold(
+        PlayerSummarization {
+            apm: 0.0,
+            pps: 0.0,
+            vs: 0.0,
+            rank: Ranks::ALL,
+            count: 0,
+            tr: 0.0,
+            glicko: 0.0,
+            rd: 0.0,
+        },
+        |mut t, x| {
+            t.apm += x.apm;
+            t.pps += x.pps;
+            t.vs += x.vs;
+            t.count += x.count;
+            t.tr += x.tr;
+            t.glicko += x.glicko;
+            t.rd += x.rd;
+            t
+        },
+    );
+    let c = total.count as f64;
+    let max_rank = stuffs
+        .iter()
+        .max_by_key(|s| s.count)
+        .map_or(Ranks::Z, |s| s.rank);
     let averaged_player_base = ProfileStats {
-        apm: (apm / c) as f32,
-        pps: (pps / c) as f32,
-        vs: (vs / c) as f32,
+        apm: (total.apm / c) as f32,
+        pps: (total.pps / c) as f32,
+        vs: (total.vs / c) as f32,
         rank: Some(max_rank),
-        tr: Some(tr / c),
+        tr: Some(total.tr / c),
         name: Some("$avgALL".to_string()),
+        id: None,
         pfp: None,
-        glicko: Some(glicko / c),
-        rd: Some(rd / c),
+        glicko: Some(total.glicko / c),
+        rd: Some(total.rd / c),
         is_real: false,
     };
     log::debug!("{:#?}", averaged_player_base);
-    locked2.push(averaged_player_base);
+    avg_players.push(averaged_player_base);
+
+    let mut distributions: std::collections::HashMap<Ranks, _> = players
+        .ranks()
+        .collect::<Vec<Ranks>>()
+        .par_iter()
+        .map(|rank| {
+            (
+                *rank,
+                tlns_tetrio_calcs::distributions(players.by_rank(*rank)),
+            )
+        })
+        .collect();
+    distributions.insert(Ranks::ALL, tlns_tetrio_calcs::distributions(players.iter()));
+    let med_players = distributions
+        .iter()
+        .map(|(rank, d)| {
+            let median = |stat: Stat| d[&stat].median();
+            ProfileStats {
+                apm: median(Stat::Apm) as f32,
+                pps: median(Stat::Pps) as f32,
+                vs: median(Stat::Vs) as f32,
+                rank: Some(match rank {
+                    Ranks::ALL => max_rank,
+                    r => *r,
+                }),
+                tr: Some(median(Stat::Tr)),
+                name: Some(format!("$med{}", rank.symbol())),
+                id: None,
+                pfp: None,
+                glicko: Some(median(Stat::Glicko)),
+                rd: Some(median(Stat::Rd)),
+                is_real: false,
+            }
+        })
+        .collect();
+    let cutoffs = tlns_tetrio_calcs::Cutoffs::from_tr(
+        players
+            .iter()
+            .filter(|p| p.rank.is_some_and(|r| r != Ranks::Z))
+            .filter_map(|p| p.tr),
+    );
+    log::info!("Indexed {} players", players.len());
+    snapshot.store(std::sync::Arc::new(state::Snapshot {
+        players,
+        avg_players,
+        med_players,
+        distributions,
+        cutoffs,
+        summaries: stuffs,
+        refreshed_at: Some(chrono::Local::now()),
+    }));
     log::info!("Done processing");
 }
 
@@ -246,12 +245,11 @@ async fn main() {
     //     .expect("Failed to connect to database");
     // let cloned = db.clone();
 
-    let player_list = std::sync::Arc::new(tokio::sync::RwLock::new(Vec::new()));
-    let average_players = std::sync::Arc::new(tokio::sync::RwLock::new(Vec::new()));
-    initialize_data(&player_list, &average_players).await;
+    let tetrio = build_tetrio_client();
+    let snapshot = std::sync::Arc::new(arc_swap::ArcSwap::from_pointee(state::Snapshot::default()));
 
-    let cloned_player_list = player_list.clone();
-    let cloned_average_players = average_players.clone();
+    let cloned_tetrio = tetrio.clone();
+    let cloned_snapshot = snapshot.clone();
     // tokio::spawn(async move {
     //     loop {
     //         tokio::time::sleep(std::time::Duration::new(5, 0)).await;
@@ -261,18 +259,19 @@ async fn main() {
     //     }
     // });
 
+    // commands fall back to the API until the first crawl lands
     tokio::spawn(async move {
         loop {
+            initialize_data(&cloned_tetrio, &cloned_snapshot).await;
             log::info!("Sleep for 5 minutes");
             tokio::time::sleep(std::time::Duration::new(300, 0)).await;
-            initialize_data(&cloned_player_list, &cloned_average_players).await;
         }
     });
 
     let s = state::States {
         up_when: chrono::Local::now(),
-        player_lists: player_list.clone(),
-        avg_players: average_players.clone(),
+        snapshot,
+        tetrio,
     };
     let bot = poise::Framework::builder()
         .options(poise::FrameworkOptions {
@@ -284,6 +283,13 @@ async fn main() {
             commands: vec![
                 commands::ts::ts(),
                 commands::vs::vs(),
+                commands::vst::vst(),
+                commands::lb::lb(),
+                commands::ranks::ranks(),
+                commands::cutoffs::cutoffs(),
+                commands::needed::needed(),
+                commands::whatif::whatif(),
+                commands::explain::explain(),
                 commands::ping::ping(),
             ],
             ..Default::default()
@@ -1,7 +1,54 @@
 #[derive(Debug, Clone)]
 pub struct States {
     // database: std::sync::Arc<tokio_postgres::Client>,
+    #[allow(dead_code)]
     pub up_when: chrono::DateTime<chrono::Local>,
-    pub player_lists: std::sync::Arc<tokio::sync::RwLock<Vec<tlns_tetrio_calcs::ProfileStats>>>,
-    pub avg_players: std::sync::Arc<tokio::sync::RwLock<Vec<tlns_tetrio_calcs::ProfileStats>>>,
+    /// Latest leaderboard crawl, replaced as a whole once a refresh finishes.
+    pub snapshot: std::sync::Arc<arc_swap::ArcSwap<Snapshot>>,
+    pub tetrio: tlns_tetrio_calcs::TetrioClient,
+}
+
+/// Players and rank statistics from the same crawl.
+#[derive(Debug, Default)]
+pub struct Snapshot {
+    pub players: crate::store::PlayerStore,
+    pub avg_players: Vec<tlns_tetrio_calcs::ProfileStats>,
+    /// `$med<Rank>` players, the median of each stat.
+    pub med_players: Vec<tlns_tetrio_calcs::ProfileStats>,
+    /// Every stat's distribution per rank, `Ranks::ALL` covers every player.
+    pub distributions: std::collections::HashMap<
+        tlns_tetrio_calcs::Ranks,
+        std::collections::HashMap<tlns_tetrio_calcs::Stat, tlns_tetrio_calcs::Distribution>,
+    >,
+    /// TR each rank currently starts at.
+    pub cutoffs: tlns_tetrio_calcs::Cutoffs,
+    /// Per-rank totals the averages were built from.
+    pub summaries: Vec<crate::PlayerSummarization>,
+    /// `None` until the first crawl finishes.
+    pub refreshed_at: Option<chrono::DateTime<chrono::Local>>,
+}
+
+impl Snapshot {
+    /// Where `player` falls for each stat among every cached player, and among their rank when
+    /// they have one.
+    pub fn percentiles(
+        &self,
+        player: &tlns_tetrio_calcs::ProfileStats,
+    ) -> Vec<(tlns_tetrio_calcs::Stat, f64, Option<f64>)> {
+        let Some(global) = self.distributions.get(&tlns_tetrio_calcs::Ranks::ALL) else {
+            return Vec::new();
+        };
+        let rank = player
+            .rank
+            .and_then(|r| self.distributions.get(&r))
+            .map(|d| tlns_tetrio_calcs::percentiles(player, d))
+            .unwrap_or_default();
+        tlns_tetrio_calcs::percentiles(player, global)
+            .into_iter()
+            .map(|(stat, pct)| {
+                let in_rank = rank.iter().find(|(s, _)| *s == stat).map(|(_, p)| *p);
+                (stat, pct, in_rank)
+            })
+            .collect()
+    }
 }
@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use tlns_tetrio_calcs::{ProfileStats, Ranks};
+
+/// Leaderboard players indexed by lowercase username, user ID and rank.
+#[derive(Debug, Default)]
+pub struct PlayerStore {
+    players: Vec<ProfileStats>,
+    by_name: HashMap<String, usize>,
+    by_id: HashMap<String, usize>,
+    by_rank: HashMap<Ranks, Vec<usize>>,
+    /// Lowercase usernames sorted for prefix search.
+    names: Vec<(String, usize)>,
+}
+
+impl PlayerStore {
+    pub fn new(players: Vec<ProfileStats>) -> Self {
+        let mut by_name = HashMap::with_capacity(players.len());
+        let mut by_id = HashMap::with_capacity(players.len());
+        let mut by_rank: HashMap<Ranks, Vec<usize>> = HashMap::new();
+        for (i, p) in players.iter().enumerate() {
+            if let Some(name) = &p.name {
+                by_name.insert(name.to_lowercase(), i);
+            }
+            if let Some(id) = &p.id {
+                by_id.insert(id.clone(), i);
+            }
+            by_rank
+                .entry(p.rank.unwrap_or(Ranks::Z))
+                .or_default()
+                .push(i);
+        }
+        let mut names: Vec<(String, usize)> =
+            by_name.iter().map(|(n, &i)| (n.clone(), i)).collect();
+        names.sort_unstable();
+        Self {
+            players,
+            by_name,
+            by_id,
+            by_rank,
+            names,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.players.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ProfileStats> {
+        self.players.iter()
+    }
+
+    /// Looks a player up by username (ignoring case) or by user ID.
+    pub fn get(&self, name_or_id: &str) -> Option<&ProfileStats> {
+        self.by_name(name_or_id).or_else(|| self.by_id(name_or_id))
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<&ProfileStats> {
+        self.by_name
+            .get(&name.to_lowercase())
+            .map(|&i| &self.players[i])
+    }
+
+    pub fn by_id(&self, id: &str) -> Option<&ProfileStats> {
+        self.by_id.get(id).map(|&i| &self.players[i])
+    }
+
+    /// Ranks that have at least one player.
+    pub fn ranks(&self) -> impl Iterator<Item = Ranks> + '_ {
+        self.by_rank.keys().copied()
+    }
+
+    pub fn by_rank(&self, rank: Ranks) -> impl Iterator<Item = &ProfileStats> {
+        self.by_rank
+            .get(&rank)
+            .into_iter()
+            .flatten()
+            .map(|&i| &self.players[i])
+    }
+
+    /// Usernames closest to `query`, for "did you mean" and autocomplete. Usernames starting
+    /// with `query` come first (shortest first), then the ones within a few typos of it. Ties go
+    /// to the player with more TR.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<&ProfileStats> {
+        let query = query.to_lowercase();
+        let query_len = query.chars().count();
+        let max_distance = (query_len / 3).clamp(1, 4);
+
+        let start = self
+            .names
+            .partition_point(|(n, _)| n.as_str() < query.as_str());
+        let mut matches: Vec<(bool, usize, usize)> = self.names[start..]
+            .iter()
+            .take_while(|(n, _)| n.starts_with(&query))
+            .map(|(n, i)| (false, n.chars().count() - query_len, *i))
+            .collect();
+        matches.extend(
+            self.names
+                .par_iter()
+                .filter(|(n, _)| {
+                    n.chars().count().abs_diff(query_len) <= max_distance && !n.starts_with(&query)
+                })
+                .filter_map(|(n, i)| {
+                    let d = strsim::levenshtein(n, &query);
+                    (d <= max_distance).then_some((true, d, *i))
+                })
+                .collect::<Vec<_>>(),
+        );
+        matches.sort_by(|a, b| {
+            (a.0, a.1).cmp(&(b.0, b.1)).then_with(|| {
+                let tr = |i: usize| self.players[i].tr.unwrap_or(0.0);
+                tr(b.2).total_cmp(&tr(a.2))
+            })
+        });
+        matches
+            .into_iter()
+            .take(limit)
+            .map(|(_, _, i)| &self.players[i])
+            .collect()
+    }
+}
@@ -1,25 +1,54 @@
-use charts_rs;
+use tlns_tetrio_calcs::{Metric, ProfileStats};
 
-pub fn plot_radar_one<const N: usize>(
-    datas: [f64; N],
-    thetas: [String; N],
+/// Radar chart of `players` over `metrics`, one series per player, each axis named after its
+/// metric's radar label. `metrics` should all have a radar weight, like
+/// [`tlns_tetrio_calcs::Stat::radar`].
+pub fn plot_metrics<M: Metric>(
+    players: &[&ProfileStats],
+    metrics: &[M],
     chart_name: String,
 ) -> Vec<u8> {
+    let thetas: Vec<String> = metrics
+        .iter()
+        .map(|m| m.radar_label().to_string())
+        .collect();
+    let mut datas: Vec<Vec<f64>> = players
+        .iter()
+        .map(|p| {
+            metrics
+                .iter()
+                .map(|m| m.radar_value(p).unwrap_or_default())
+                .collect()
+        })
+        .collect();
+    match players {
+        [_] => plot_radar_one(datas.remove(0), thetas, chart_name),
+        _ => plot_radar_multiple(
+            datas,
+            thetas,
+            players
+                .iter()
+                .map(|p| p.name.clone().unwrap_or_default())
+                .collect(),
+            chart_name,
+        ),
+    }
+}
+
+pub fn plot_radar_one(datas: Vec<f64>, thetas: Vec<String>, chart_name: String) -> Vec<u8> {
     // thetas.reverse();
     let width = 800;
     let height = 600;
     let mut chart = charts_rs::RadarChart::new(
         vec![charts_rs::Series::new(
             "".to_string(),
-            datas.map(|i| i as f32).to_vec(),
+            datas.iter().map(|i| *i as f32).collect(),
         )],
         thetas
             .iter()
-            .map(|i| {
-                let mut a = charts_rs::RadarIndicator::default();
-                a.max = 180.0;
-                a.name = i.clone();
-                a
+            .map(|i| charts_rs::RadarIndicator {
+                max: 180.0,
+                name: i.clone(),
             })
             .collect(),
     );
@@ -64,11 +93,9 @@ pub fn plot_radar_multiple(
             .collect(),
         thetas
             .iter()
-            .map(|i| {
-                let mut a = charts_rs::RadarIndicator::default();
-                a.max = 180.0;
-                a.name = i.clone();
-                a
+            .map(|i| charts_rs::RadarIndicator {
+                max: 180.0,
+                name: i.clone(),
             })
             .collect(),
     );
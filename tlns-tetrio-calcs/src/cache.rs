@@ -0,0 +1,118 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::models::CacheInfo;
+
+/// Data returned by [`crate::TetrioClient`] together with ch.tetr.io's cache metadata.
+#[derive(Debug, Clone)]
+pub struct Fetched<T> {
+    pub data: T,
+    pub cache: Option<CacheInfo>,
+    /// Whether the data was served from the client's own cache without a request.
+    pub hit: bool,
+}
+
+impl<T> Fetched<T> {
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Fetched<U> {
+        Fetched {
+            data: f(self.data),
+            cache: self.cache,
+            hit: self.hit,
+        }
+    }
+
+    /// When ch.tetr.io produced this data.
+    pub fn as_of(&self) -> Option<SystemTime> {
+        self.cache
+            .map(|c| UNIX_EPOCH + std::time::Duration::from_millis(c.cached_at))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct CacheKey {
+    pub endpoint: &'static str,
+    pub user: String,
+}
+
+#[derive(Debug)]
+struct Entry {
+    data: serde_json::Value,
+    cache: CacheInfo,
+}
+
+/// In-process cache of response bodies, each kept until its `cache.cached_until`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ResponseCache {
+    entries: Arc<Mutex<HashMap<CacheKey, Entry>>>,
+}
+
+impl ResponseCache {
+    pub fn get(&self, key: &CacheKey) -> Option<(serde_json::Value, CacheInfo)> {
+        let mut entries = self.entries.lock().unwrap();
+        match entries.get(key) {
+            Some(e) if e.cache.cached_until > now_millis() => Some((e.data.clone(), e.cache)),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn insert(&self, key: CacheKey, data: serde_json::Value, cache: CacheInfo) {
+        let now = now_millis();
+        if cache.cached_until <= now {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, e| e.cache.cached_until > now);
+        entries.insert(key, Entry { data, cache });
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(all(test, feature = "test"))]
+mod tests {
+    use super::*;
+    use crate::models::CacheStatus;
+
+    fn key() -> CacheKey {
+        CacheKey {
+            endpoint: "users",
+            user: "osk".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_serves_until_cached_until() {
+        let cache = ResponseCache::default();
+        let info = CacheInfo {
+            status: CacheStatus::Miss,
+            cached_at: now_millis(),
+            cached_until: now_millis() + 60_000,
+        };
+        cache.insert(key(), serde_json::json!({"a": 1}), info);
+        assert_eq!(cache.get(&key()).unwrap().0["a"], 1);
+    }
+
+    #[test]
+    fn test_expired_is_dropped() {
+        let cache = ResponseCache::default();
+        let info = CacheInfo {
+            status: CacheStatus::Miss,
+            cached_at: 0,
+            cached_until: 1,
+        };
+        cache.insert(key(), serde_json::json!({"a": 1}), info);
+        assert!(cache.get(&key()).is_none());
+    }
+}
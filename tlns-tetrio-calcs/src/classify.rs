@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+
+use crate::{Distribution, Metric, ProfileStats, Ranks, Stat};
+
+/// Stats the classifier compares. None of them need TR, so made up players work too.
+pub const CLASSIFIER_STATS: &[Stat] = &[
+    Stat::Apm,
+    Stat::Pps,
+    Stat::Vs,
+    Stat::App,
+    Stat::DsPiece,
+    Stat::DsSecond,
+    Stat::VsApm,
+    Stat::GarbageEfficiency,
+];
+
+/// How close a player's stats are to a rank's typical player.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RankMatch {
+    pub rank: Ranks,
+    /// Root mean square of the differences to the rank's medians, each in global standard
+    /// deviations.
+    pub distance: f64,
+    /// `distance` mapped to 0 to 100, 100 being identical.
+    pub similarity: f64,
+}
+
+/// The `limit` ranks whose median player is closest to `player`, closest first.
+///
+/// `distributions` is keyed by rank like the bot's snapshot, with [`Ranks::ALL`] covering every
+/// player. That one is only used to scale the stats so APM and APP weigh the same.
+pub fn classify(
+    player: &ProfileStats,
+    distributions: &HashMap<Ranks, HashMap<Stat, Distribution>>,
+    limit: usize,
+) -> Vec<RankMatch> {
+    let Some(global) = distributions.get(&Ranks::ALL) else {
+        return Vec::new();
+    };
+    let scales: Vec<(Stat, f64, f64)> = CLASSIFIER_STATS
+        .iter()
+        .filter_map(|stat| {
+            let value = stat.of(player).filter(|v| v.is_finite())?;
+            let scale = global.get(stat)?.stddev();
+            (scale.is_finite() && scale > 0.0).then_some((*stat, value, scale))
+        })
+        .collect();
+    if scales.is_empty() {
+        return Vec::new();
+    }
+
+    let mut matches: Vec<RankMatch> = distributions
+        .iter()
+        .filter(|(rank, _)| !matches!(rank, Ranks::ALL | Ranks::Z))
+        .filter_map(|(rank, d)| {
+            let mut sum = 0.0;
+            for (stat, value, scale) in &scales {
+                let median = d.get(stat)?.median();
+                if !median.is_finite() {
+                    return None;
+                }
+                sum += ((value - median) / scale).powi(2);
+            }
+            let distance = (sum / scales.len() as f64).sqrt();
+            Some(RankMatch {
+                rank: *rank,
+                distance,
+                similarity: (-distance.powi(2) / 2.0).exp() * 100.0,
+            })
+        })
+        .collect();
+    matches.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+    matches.truncate(limit);
+    matches
+}
+
+#[cfg(all(test, feature = "test"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_closest_rank_first() {
+        let low = [
+            ProfileStats::from_stat(20.0, 1.0, 40.0),
+            ProfileStats::from_stat(25.0, 1.1, 50.0),
+        ];
+        let high = [
+            ProfileStats::from_stat(120.0, 3.0, 250.0),
+            ProfileStats::from_stat(130.0, 3.2, 270.0),
+        ];
+        let mut distributions = HashMap::new();
+        distributions.insert(Ranks::D, crate::distributions(&low));
+        distributions.insert(Ranks::X, crate::distributions(&high));
+        distributions.insert(Ranks::ALL, crate::distributions(low.iter().chain(&high)));
+
+        let matches = classify(
+            &ProfileStats::from_stat(118.0, 3.1, 255.0),
+            &distributions,
+            5,
+        );
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].rank, Ranks::X);
+        assert!(matches[0].similarity > matches[1].similarity);
+        assert!(matches[0].similarity <= 100.0);
+
+        assert!(classify(&low[0], &HashMap::new(), 5).is_empty());
+    }
+}
@@ -0,0 +1,604 @@
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+
+use crate::{
+    cache::{CacheKey, Fetched, ResponseCache},
+    models::{GeneralStats, LeagueSummary, Response, User},
+    ratelimit::{backoff, Priority, RateLimiter},
+    Errors, ProfileStats, API,
+};
+
+pub const DEFAULT_USER_AGENT: &str = "tlns-tetrio-calcs";
+
+/// Longest `Retry-After` that's waited out. The rate limiter is shared, so honoring a longer one
+/// would stall every other request too; it's returned as [`Errors::RateLimited`] instead.
+pub const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// A ch.tetr.io API client that owns one pooled HTTP client.
+///
+/// Cloning is cheap (the underlying [`reqwest::Client`] is reference counted), so the same
+/// connection pool can be shared between the bot, the leaderboard crawler and scripts.
+#[derive(Debug, Clone)]
+pub struct TetrioClient {
+    http: reqwest::Client,
+    base_url: String,
+    session_id: Option<String>,
+    cache: Option<ResponseCache>,
+    limiter: RateLimiter,
+    priority: Priority,
+    max_retries: u32,
+}
+
+/// Configuration for [`TetrioClient`], see [`TetrioClient::builder`].
+#[derive(Debug, Clone)]
+pub struct TetrioClientBuilder {
+    base_url: String,
+    user_agent: String,
+    session_id: Option<String>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    proxy: Option<String>,
+    response_cache: bool,
+    requests_per_second: f64,
+    burst: u32,
+    max_retries: u32,
+}
+
+impl Default for TetrioClientBuilder {
+    fn default() -> Self {
+        Self {
+            base_url: API.to_string(),
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            session_id: None,
+            timeout: Some(Duration::from_secs(30)),
+            connect_timeout: Some(Duration::from_secs(10)),
+            proxy: None,
+            response_cache: true,
+            requests_per_second: 1.0,
+            burst: 5,
+            max_retries: 5,
+        }
+    }
+}
+
+impl TetrioClientBuilder {
+    /// Base URL of the API, defaults to [`API`]. Point this at a mock server for tests.
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        let mut base_url = base_url.into();
+        if !base_url.ends_with('/') {
+            base_url.push('/');
+        }
+        self.base_url = base_url;
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Value of the `X-Session-ID` header, which ch.tetr.io uses to keep paginated results consistent.
+    pub fn session_id(mut self, session_id: impl Into<String>) -> Self {
+        self.session_id = Some(session_id.into());
+        self
+    }
+
+    /// Timeout for a whole request, `None` disables it.
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn connect_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    /// Proxy URL every request is sent through.
+    pub fn proxy(mut self, proxy: impl Into<String>) -> Self {
+        self.proxy = Some(proxy.into());
+        self
+    }
+
+    /// Whether to keep responses until ch.tetr.io's `cache.cached_until`, enabled by default.
+    pub fn response_cache(mut self, enabled: bool) -> Self {
+        self.response_cache = enabled;
+        self
+    }
+
+    /// Token bucket shared by every clone of the built client, `burst` requests can go out at once.
+    /// [`Self::build`] fails unless `requests_per_second` is above 0.
+    pub fn rate_limit(mut self, requests_per_second: f64, burst: u32) -> Self {
+        self.requests_per_second = requests_per_second;
+        self.burst = burst;
+        self
+    }
+
+    /// How many times a rate limited, 5xx or timed out request is retried.
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    pub fn build(self) -> Result<TetrioClient, Errors> {
+        if !(self.requests_per_second.is_finite() && self.requests_per_second > 0.0) {
+            return Err(Errors::InvalidRateLimit(self.requests_per_second));
+        }
+        let mut builder = reqwest::ClientBuilder::new().user_agent(self.user_agent);
+        if let Some(t) = self.timeout {
+            builder = builder.timeout(t);
+        }
+        if let Some(t) = self.connect_timeout {
+            builder = builder.connect_timeout(t);
+        }
+        if let Some(p) = self.proxy {
+            builder = builder.proxy(reqwest::Proxy::all(p).map_err(Errors::FailedToBuildClient)?);
+        }
+        Ok(TetrioClient {
+            http: builder.build().map_err(Errors::FailedToBuildClient)?,
+            base_url: self.base_url,
+            session_id: self.session_id,
+            cache: self.response_cache.then(ResponseCache::default),
+            limiter: RateLimiter::new(self.requests_per_second, self.burst),
+            priority: Priority::Interactive,
+            max_retries: self.max_retries,
+        })
+    }
+}
+
+impl TetrioClient {
+    /// A client with the default configuration.
+    pub fn new() -> Result<Self, Errors> {
+        Self::builder().build()
+    }
+
+    pub fn builder() -> TetrioClientBuilder {
+        TetrioClientBuilder::default()
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn session_id(&self) -> Option<&str> {
+        self.session_id.as_deref()
+    }
+
+    /// A clone sharing the pool, cache and rate limiter whose requests have `priority`.
+    pub fn with_priority(&self, priority: Priority) -> Self {
+        Self {
+            priority,
+            ..self.clone()
+        }
+    }
+
+    /// Shorthand for [`Self::with_priority`] with [`Priority::Background`].
+    pub fn background(&self) -> Self {
+        self.with_priority(Priority::Background)
+    }
+
+    /// Starts a GET request to `endpoint` (relative to the base URL) with the session ID attached.
+    pub fn get(&self, endpoint: &str) -> reqwest::RequestBuilder {
+        let req = self.http.get(self.base_url.clone() + endpoint);
+        match &self.session_id {
+            Some(s) => req.header("X-Session-ID", s),
+            None => req,
+        }
+    }
+
+    /// Sends a GET request to `endpoint` and parses the response envelope.
+    ///
+    /// Rate limits, server errors and `success: false` responses are turned into their own
+    /// [`Errors`] variants, so a returned [`Response`] is always successful. Requests wait for the
+    /// rate limiter and retryable failures are retried with backoff, honoring `Retry-After` up to
+    /// [`MAX_RETRY_AFTER`].
+    pub async fn get_response<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        query: &[(&str, &str)],
+    ) -> Result<Response<T>, Errors> {
+        let mut attempt = 0;
+        loop {
+            self.limiter.acquire(self.priority).await;
+            match self.send_once(endpoint, query).await {
+                Err(e)
+                    if e.is_retryable()
+                        && attempt < self.max_retries
+                        && e.retry_after().is_none_or(|d| d <= MAX_RETRY_AFTER) =>
+                {
+                    let wait = match e.retry_after() {
+                        Some(d) => {
+                            self.limiter.pause_for(d).await;
+                            d
+                        }
+                        None => {
+                            backoff(Duration::from_millis(500), Duration::from_secs(30), attempt)
+                        }
+                    };
+                    attempt += 1;
+                    tokio::time::sleep(wait).await;
+                }
+                r => return r,
+            }
+        }
+    }
+
+    async fn send_once<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        query: &[(&str, &str)],
+    ) -> Result<Response<T>, Errors> {
+        let response = self
+            .get(endpoint)
+            .query(query)
+            .send()
+            .await
+            .map_err(Errors::FailedToSendRequest)?;
+        let status = response.status();
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(Errors::RateLimited {
+                retry_after: retry_after(response.headers()),
+            });
+        }
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(Errors::FailedToSendRequest)?;
+        if status.is_server_error() {
+            return Err(Errors::ServerError {
+                status: status.as_u16(),
+                message: serde_json::from_slice::<Response<serde::de::IgnoredAny>>(&bytes)
+                    .ok()
+                    .and_then(|r| r.error)
+                    .map(|e| e.message().to_string()),
+            });
+        }
+        let parsed = match serde_json::from_slice::<Response<T>>(&bytes) {
+            Ok(p) => p,
+            Err(_) if !status.is_success() => {
+                return Err(Errors::Api {
+                    status: status.as_u16(),
+                    message: status.to_string(),
+                })
+            }
+            Err(e) => return Err(Errors::MalformedResponse(e)),
+        };
+        if !parsed.success || !status.is_success() {
+            return Err(Errors::Api {
+                status: status.as_u16(),
+                message: parsed
+                    .error
+                    .map(|e| e.message().to_string())
+                    .unwrap_or_else(|| status.to_string()),
+            });
+        }
+        Ok(parsed)
+    }
+
+    /// Like [`Self::get_response`], but serves the data from the response cache while it's fresh.
+    async fn get_cached<T: DeserializeOwned>(
+        &self,
+        key: CacheKey,
+        endpoint: &str,
+    ) -> Result<Fetched<T>, Errors> {
+        if let Some((data, info)) = self.cache.as_ref().and_then(|c| c.get(&key)) {
+            return Ok(Fetched {
+                data: T::deserialize(&data).map_err(Errors::MalformedResponse)?,
+                cache: Some(info),
+                hit: true,
+            });
+        }
+        let response = self
+            .get_response::<serde_json::Value>(endpoint, &[])
+            .await?;
+        let info = response.cache;
+        let data = response.into_data()?;
+        let parsed = T::deserialize(&data).map_err(Errors::MalformedResponse)?;
+        if let (Some(cache), Some(info)) = (&self.cache, info) {
+            cache.insert(key, data, info);
+        }
+        Ok(Fetched {
+            data: parsed,
+            cache: info,
+            hit: false,
+        })
+    }
+
+    /// `GET users/:user`
+    pub async fn user(&self, username: &str) -> Result<Fetched<User>, Errors> {
+        let username = username.to_lowercase();
+        self.get_cached(
+            CacheKey {
+                endpoint: "users",
+                user: username.clone(),
+            },
+            &format!("users/{username}"),
+        )
+        .await
+    }
+
+    /// `GET users/:user/summaries/league`
+    pub async fn league_summary(&self, username: &str) -> Result<Fetched<LeagueSummary>, Errors> {
+        let username = username.to_lowercase();
+        self.get_cached(
+            CacheKey {
+                endpoint: "summaries/league",
+                user: username.clone(),
+            },
+            &format!("users/{username}/summaries/league"),
+        )
+        .await
+    }
+
+    /// `GET general/stats`
+    pub async fn general_stats(&self) -> Result<GeneralStats, Errors> {
+        self.get_response::<GeneralStats>("general/stats", &[])
+            .await?
+            .into_data()
+    }
+
+    /// Fetches a user's TETRA LEAGUE profile, the cache metadata is the league summary's.
+    pub async fn profile(&self, username: &str) -> Result<Fetched<ProfileStats>, Errors> {
+        let not_found = |e: Errors| match e {
+            Errors::Api { status: 404, .. } => Errors::UserNotFound(username.to_string()),
+            Errors::Api { ref message, .. } if message.starts_with("No such user") => {
+                Errors::UserNotFound(username.to_string())
+            }
+            e => e,
+        };
+        let user = self.user(username).await.map_err(not_found)?.data;
+        match user.role.as_str() {
+            "banned" => return Err(Errors::UserBanned(user.username)),
+            "anon" => return Err(Errors::AnonymousUser(user.username)),
+            _ => {}
+        }
+        let league = self.league_summary(username).await.map_err(not_found)?;
+        if league.data.gamesplayed == 0 || league.data.apm.is_none() {
+            return Err(Errors::Unranked(user.username));
+        }
+        Ok(league.map(|l| ProfileStats::from_league(&user, &l)))
+    }
+}
+
+/// Reads a `Retry-After` header given in seconds, `None` when it's missing or not a duration.
+fn retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    headers
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .and_then(|s| Duration::try_from_secs_f64(s).ok())
+}
+
+#[cfg(all(test, feature = "test"))]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    /// `(status, extra headers, body)` served by [`mock`].
+    type Canned = (u16, &'static str, &'static str);
+
+    const USER: &str = r#"{"success":true,"data":{"_id":"abc","username":"osk","role":"user"}}"#;
+    const LEAGUE: &str = r#"{"success":true,"data":{"gamesplayed":10,"gameswon":5,"glicko":2257.86,"rd":66.04,"tr":23684.48,"rank":"u","apm":66.09,"pps":2.07,"vs":135.65}}"#;
+
+    /// A client pointed at a local server answering requests with `responses` in order, and how
+    /// many requests the server got.
+    async fn mock(responses: Vec<Canned>, max_retries: u32) -> (TetrioClient, Arc<AtomicUsize>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        tokio::spawn(async move {
+            for (status, headers, body) in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match socket.read(&mut buf).await.unwrap() {
+                        0 => break,
+                        n => request.extend_from_slice(&buf[..n]),
+                    }
+                }
+                counter.fetch_add(1, Ordering::SeqCst);
+                let response = format!(
+                    "HTTP/1.1 {status} Canned\r\ncontent-length: {}\r\nconnection: close\r\n{headers}\r\n{body}",
+                    body.len()
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        let client = TetrioClient::builder()
+            .base_url(format!("http://{addr}"))
+            .rate_limit(1000.0, 100)
+            .response_cache(false)
+            .max_retries(max_retries)
+            .build()
+            .unwrap();
+        (client, requests)
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited() {
+        let (client, requests) =
+            mock(vec![(429, "retry-after: 0\r\n", ""), (200, "", USER)], 1).await;
+        assert_eq!(client.user("osk").await.unwrap().data.username, "osk");
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+        let (client, requests) = mock(
+            vec![(429, "retry-after: 86400\r\n", ""), (200, "", USER)],
+            1,
+        )
+        .await;
+        let e = client.user("osk").await.unwrap_err();
+        assert_eq!(e.retry_after(), Some(Duration::from_secs(86400)));
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        let (client, _) = mock(vec![(429, "", "")], 0).await;
+        assert!(matches!(
+            client.user("osk").await,
+            Err(Errors::RateLimited { retry_after: None })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_server_error() {
+        let (client, _) = mock(
+            vec![(503, "", r#"{"success":false,"error":{"msg":"down"}}"#)],
+            0,
+        )
+        .await;
+        match client.user("osk").await {
+            Err(Errors::ServerError { status, message }) => {
+                assert_eq!(status, 503);
+                assert_eq!(message.as_deref(), Some("down"));
+            }
+            r => panic!("expected a server error, got {r:?}"),
+        }
+
+        let (client, requests) = mock(vec![(502, "", "<html>"), (200, "", USER)], 1).await;
+        assert!(client.user("osk").await.is_ok());
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_api_error() {
+        let (client, _) = mock(
+            vec![(400, "", r#"{"success":false,"error":{"msg":"bad query"}}"#)],
+            0,
+        )
+        .await;
+        match client.user("osk").await {
+            Err(Errors::Api { status, message }) => {
+                assert_eq!(status, 400);
+                assert_eq!(message, "bad query");
+            }
+            r => panic!("expected an API error, got {r:?}"),
+        }
+
+        let (client, _) = mock(vec![(403, "", "<html>")], 0).await;
+        assert!(matches!(
+            client.user("osk").await,
+            Err(Errors::Api { status: 403, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_malformed_response() {
+        let (client, _) = mock(vec![(200, "", "not json")], 0).await;
+        assert!(matches!(
+            client.user("osk").await,
+            Err(Errors::MalformedResponse(_))
+        ));
+        let (client, _) = mock(vec![(200, "", r#"{"success":true}"#)], 0).await;
+        assert!(matches!(
+            client.user("osk").await,
+            Err(Errors::MalformedResponse(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_profile() {
+        let (client, _) = mock(vec![(200, "", USER), (200, "", LEAGUE)], 0).await;
+        let profile = client.profile("osk").await.unwrap().data;
+        assert_eq!(profile.name.as_deref(), Some("osk"));
+        assert_eq!(profile.tr, Some(23684.48));
+
+        let no_such_user = r#"{"success":false,"error":{"msg":"No such user! | Either you mistyped something, or the account no longer exists."}}"#;
+        let (client, _) = mock(vec![(200, "", no_such_user)], 0).await;
+        assert!(matches!(
+            client.profile("osk").await,
+            Err(Errors::UserNotFound(u)) if u == "osk"
+        ));
+        let (client, _) = mock(vec![(404, "", "")], 0).await;
+        assert!(matches!(
+            client.profile("osk").await,
+            Err(Errors::UserNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_profile_user_states() {
+        let (client, _) = mock(
+            vec![(
+                200,
+                "",
+                r#"{"success":true,"data":{"_id":"abc","username":"osk","role":"banned"}}"#,
+            )],
+            0,
+        )
+        .await;
+        assert!(matches!(
+            client.profile("osk").await,
+            Err(Errors::UserBanned(_))
+        ));
+
+        let (client, _) = mock(
+            vec![(
+                200,
+                "",
+                r#"{"success":true,"data":{"_id":"abc","username":"osk","role":"anon"}}"#,
+            )],
+            0,
+        )
+        .await;
+        assert!(matches!(
+            client.profile("osk").await,
+            Err(Errors::AnonymousUser(_))
+        ));
+
+        let (client, _) = mock(
+            vec![
+                (200, "", USER),
+                (
+                    200,
+                    "",
+                    r#"{"success":true,"data":{"gamesplayed":0,"gameswon":0,"rank":"z"}}"#,
+                ),
+            ],
+            0,
+        )
+        .await;
+        assert!(matches!(
+            client.profile("osk").await,
+            Err(Errors::Unranked(_))
+        ));
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+        headers.insert(reqwest::header::RETRY_AFTER, "12".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(12)));
+        headers.insert(reqwest::header::RETRY_AFTER, "-1".parse().unwrap());
+        assert_eq!(retry_after(&headers), None);
+        headers.insert(reqwest::header::RETRY_AFTER, "1e300".parse().unwrap());
+        assert_eq!(retry_after(&headers), None);
+    }
+
+    #[test]
+    fn test_invalid_rate_limit() {
+        for rate in [0.0, -1.0, f64::NAN] {
+            let client = TetrioClient::builder().rate_limit(rate, 1).build();
+            assert!(matches!(client, Err(Errors::InvalidRateLimit(_))));
+        }
+    }
+
+    #[test]
+    fn test_base_url_trailing_slash() {
+        let client = TetrioClient::builder()
+            .base_url("http://127.0.0.1:8080/api")
+            .build()
+            .unwrap();
+        assert_eq!(client.base_url(), "http://127.0.0.1:8080/api/");
+    }
+}
@@ -0,0 +1,193 @@
+use crate::{ProfileStats, Ranks};
+
+/// How far down the leaderboard (top percent of ranked players) each rank reaches, top rank
+/// first. These are TETR.IO's rank bands.
+pub const RANK_PERCENTILES: &[(Ranks, f64)] = &[
+    (Ranks::XPlus, 0.2),
+    (Ranks::X, 1.0),
+    (Ranks::U, 5.0),
+    (Ranks::SS, 11.0),
+    (Ranks::SPlus, 17.0),
+    (Ranks::S, 23.0),
+    (Ranks::SMinus, 30.0),
+    (Ranks::APlus, 38.0),
+    (Ranks::A, 46.0),
+    (Ranks::AMinus, 54.0),
+    (Ranks::BPlus, 62.0),
+    (Ranks::B, 70.0),
+    (Ranks::BMinus, 78.0),
+    (Ranks::CPlus, 84.0),
+    (Ranks::C, 90.0),
+    (Ranks::CMinus, 95.0),
+    (Ranks::DPlus, 97.5),
+    (Ranks::D, 100.0),
+];
+
+/// The TR each rank currently starts at, derived from where the rank bands fall on the
+/// leaderboard.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Cutoffs {
+    /// Top rank first, like [`RANK_PERCENTILES`].
+    cutoffs: Vec<(Ranks, f64)>,
+    /// TR of every ranked player, highest first.
+    tr: Vec<f64>,
+}
+
+/// Where a player's stats alone would place them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Prediction {
+    pub estimated_glicko: f64,
+    pub estimated_tr: f64,
+    pub rank: Ranks,
+    /// 1-based leaderboard position the estimated TR would have.
+    pub position: usize,
+}
+
+impl Cutoffs {
+    /// Computes cutoffs from the TR of every ranked player.
+    pub fn from_tr(tr: impl IntoIterator<Item = f64>) -> Self {
+        let mut tr: Vec<f64> = tr.into_iter().filter(|t| t.is_finite()).collect();
+        if tr.is_empty() {
+            return Self::default();
+        }
+        tr.sort_unstable_by(|a, b| b.total_cmp(a));
+        let n = tr.len();
+        let cutoffs = RANK_PERCENTILES
+            .iter()
+            .map(|(rank, pct)| {
+                // the last player still inside the band sets the cutoff
+                let index = ((n as f64 * pct / 100.0).ceil() as usize).clamp(1, n) - 1;
+                (*rank, tr[index])
+            })
+            .collect();
+        Self { cutoffs, tr }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cutoffs.is_empty()
+    }
+
+    /// Number of ranked players the cutoffs came from.
+    pub fn players(&self) -> usize {
+        self.tr.len()
+    }
+
+    /// 1-based leaderboard position a player with `tr` would have.
+    pub fn position_for(&self, tr: f64) -> usize {
+        self.tr.partition_point(|t| *t > tr) + 1
+    }
+
+    /// Places `player` on the leaderboard by their estimated TR, for made up players that
+    /// don't have a real one. `None` before any cutoffs are known, or when the stats give no
+    /// usable estimate.
+    pub fn predict(&self, player: &ProfileStats) -> Option<Prediction> {
+        let estimated_tr = player.estimated_tr();
+        if !estimated_tr.is_finite() {
+            return None;
+        }
+        Some(Prediction {
+            estimated_glicko: player.estimated_glicko(),
+            estimated_tr,
+            rank: self.rank_for(estimated_tr)?,
+            position: self.position_for(estimated_tr),
+        })
+    }
+
+    /// Each rank with the TR it starts at, top rank first.
+    pub fn iter(&self) -> impl Iterator<Item = (Ranks, f64)> + '_ {
+        self.cutoffs.iter().copied()
+    }
+
+    pub fn get(&self, rank: Ranks) -> Option<f64> {
+        self.iter().find(|(r, _)| *r == rank).map(|(_, tr)| tr)
+    }
+
+    /// The rank `tr` falls into. Anything below the D cutoff still counts as D.
+    pub fn rank_for(&self, tr: f64) -> Option<Ranks> {
+        self.iter()
+            .find(|(_, cutoff)| tr >= *cutoff)
+            .or(self.cutoffs.last().copied())
+            .map(|(r, _)| r)
+    }
+
+    /// The next rank up from `tr` and how much TR it takes to get there.
+    pub fn next_up(&self, tr: f64) -> Option<(Ranks, f64)> {
+        let rank = self.rank_for(tr)?;
+        let index = self.cutoffs.iter().position(|(r, _)| *r == rank)?;
+        let (next, cutoff) = *self.cutoffs.get(index.checked_sub(1)?)?;
+        Some((next, cutoff - tr))
+    }
+
+    /// The rank `tr` drops to once it falls below its current rank's cutoff, and how much TR
+    /// that is away.
+    pub fn next_down(&self, tr: f64) -> Option<(Ranks, f64)> {
+        let rank = self.rank_for(tr)?;
+        let index = self.cutoffs.iter().position(|(r, _)| *r == rank)?;
+        let (next, _) = *self.cutoffs.get(index + 1)?;
+        Some((next, tr - self.cutoffs[index].1))
+    }
+}
+
+#[cfg(all(test, feature = "test"))]
+mod tests {
+    use super::*;
+
+    /// 1000 players at 1, 2, ..., 1000 TR.
+    fn cutoffs() -> Cutoffs {
+        Cutoffs::from_tr((1..=1000).map(|t| t as f64))
+    }
+
+    #[test]
+    fn test_bands() {
+        let c = cutoffs();
+        assert_eq!(c.players(), 1000);
+        assert_eq!(c.get(Ranks::XPlus), Some(999.0));
+        assert_eq!(c.get(Ranks::X), Some(991.0));
+        assert_eq!(c.get(Ranks::AMinus), Some(461.0));
+        assert_eq!(c.get(Ranks::D), Some(1.0));
+    }
+
+    #[test]
+    fn test_rank_for() {
+        let c = cutoffs();
+        assert_eq!(c.rank_for(1000.0), Some(Ranks::XPlus));
+        assert_eq!(c.rank_for(995.0), Some(Ranks::X));
+        assert_eq!(c.rank_for(0.0), Some(Ranks::D));
+        assert_eq!(Cutoffs::default().rank_for(100.0), None);
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let c = cutoffs();
+        assert_eq!(c.next_up(995.0), Some((Ranks::XPlus, 4.0)));
+        assert_eq!(c.next_down(995.0), Some((Ranks::U, 4.0)));
+        assert_eq!(c.next_up(1000.0), None);
+        assert_eq!(c.next_down(1.0), None);
+    }
+
+    #[test]
+    fn test_position() {
+        let c = cutoffs();
+        assert_eq!(c.position_for(1001.0), 1);
+        assert_eq!(c.position_for(1000.0), 1);
+        assert_eq!(c.position_for(999.5), 2);
+        assert_eq!(c.position_for(0.0), 1001);
+    }
+
+    #[test]
+    fn test_predict() {
+        let player = ProfileStats::from_stat(60.0, 2.0, 120.0);
+        assert_eq!(Cutoffs::default().predict(&player), None);
+        let prediction = cutoffs().predict(&player).unwrap();
+        assert_eq!(prediction.estimated_tr, player.estimated_tr());
+        assert_eq!(prediction.rank, Ranks::XPlus);
+    }
+
+    #[test]
+    fn test_predict_non_finite() {
+        // no PPS makes every per-piece stat infinite
+        let player = ProfileStats::from_stat(60.0, 0.0, 120.0);
+        assert!(!player.estimated_tr().is_finite());
+        assert_eq!(cutoffs().predict(&player), None);
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+use crate::{Metric, ProfileStats, Stat};
+
+/// Every value of one stat across a group of players, sorted so quantiles are cheap.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Distribution {
+    sorted: Vec<f64>,
+    mean: f64,
+}
+
+impl Distribution {
+    /// Builds a distribution, skipping values that aren't finite (like APP of a 0 PPS player).
+    pub fn new(values: impl IntoIterator<Item = f64>) -> Self {
+        let mut sorted: Vec<f64> = values.into_iter().filter(|v| v.is_finite()).collect();
+        sorted.sort_unstable_by(f64::total_cmp);
+        let mean = match sorted.len() {
+            0 => f64::NAN,
+            n => sorted.iter().sum::<f64>() / n as f64,
+        };
+        Self { sorted, mean }
+    }
+
+    pub fn count(&self) -> usize {
+        self.sorted.len()
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    pub fn median(&self) -> f64 {
+        self.quantile(0.5)
+    }
+
+    /// Population standard deviation.
+    pub fn stddev(&self) -> f64 {
+        if self.sorted.is_empty() {
+            return f64::NAN;
+        }
+        let variance = self
+            .sorted
+            .iter()
+            .map(|v| (v - self.mean).powi(2))
+            .sum::<f64>()
+            / self.sorted.len() as f64;
+        variance.sqrt()
+    }
+
+    /// The value below which `q` (0 to 1) of the players fall, linearly interpolated between
+    /// the two closest players. NaN when empty.
+    pub fn quantile(&self, q: f64) -> f64 {
+        let Some(last) = self.sorted.len().checked_sub(1) else {
+            return f64::NAN;
+        };
+        let pos = q.clamp(0.0, 1.0) * last as f64;
+        let (lo, hi) = (pos.floor() as usize, pos.ceil() as usize);
+        self.sorted[lo] + (self.sorted[hi] - self.sorted[lo]) * (pos - lo as f64)
+    }
+
+    /// Share of players (0 to 100) with a lower value than `value`, ties count as half. NaN when
+    /// empty.
+    pub fn percentile_of(&self, value: f64) -> f64 {
+        if self.sorted.is_empty() || !value.is_finite() {
+            return f64::NAN;
+        }
+        let below = self.sorted.partition_point(|v| *v < value);
+        let up_to = self.sorted.partition_point(|v| *v <= value);
+        (below + up_to) as f64 / 2.0 / self.sorted.len() as f64 * 100.0
+    }
+
+    pub fn min(&self) -> f64 {
+        self.sorted.first().copied().unwrap_or(f64::NAN)
+    }
+
+    pub fn max(&self) -> f64 {
+        self.sorted.last().copied().unwrap_or(f64::NAN)
+    }
+}
+
+/// Distributions of every [`Stat`] across `players`.
+pub fn distributions<'a>(
+    players: impl IntoIterator<Item = &'a ProfileStats>,
+) -> HashMap<Stat, Distribution> {
+    let players: Vec<&ProfileStats> = players.into_iter().collect();
+    Stat::ALL
+        .iter()
+        .map(|stat| {
+            (
+                *stat,
+                Distribution::new(players.iter().filter_map(|p| stat.of(p))),
+            )
+        })
+        .collect()
+}
+
+/// Where `player` falls in each of `distributions`, in [`Stat::ALL`] order. Stats the player
+/// doesn't have (like TR of a made up player) or that nobody has are left out.
+pub fn percentiles(
+    player: &ProfileStats,
+    distributions: &HashMap<Stat, Distribution>,
+) -> Vec<(Stat, f64)> {
+    Stat::ALL
+        .iter()
+        .filter_map(|stat| {
+            let pct = distributions.get(stat)?.percentile_of(stat.of(player)?);
+            (!pct.is_nan()).then_some((*stat, pct))
+        })
+        .collect()
+}
+
+#[cfg(all(test, feature = "test"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        let d = Distribution::new([4.0, 1.0, 3.0, 2.0, f64::NAN, 5.0]);
+        assert_eq!(d.count(), 5);
+        assert_eq!(d.mean(), 3.0);
+        assert_eq!(d.median(), 3.0);
+        assert_eq!(d.min(), 1.0);
+        assert_eq!(d.max(), 5.0);
+        assert_eq!(d.quantile(0.25), 2.0);
+        assert_eq!(d.quantile(0.1), 1.4);
+        assert!((d.stddev() - 2.0_f64.sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_percentile_of() {
+        let d = Distribution::new([1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(d.percentile_of(0.0), 0.0);
+        assert_eq!(d.percentile_of(2.5), 50.0);
+        assert_eq!(d.percentile_of(2.0), 37.5);
+        assert_eq!(d.percentile_of(10.0), 100.0);
+        assert!(d.percentile_of(f64::NAN).is_nan());
+    }
+
+    #[test]
+    fn test_empty() {
+        let d = Distribution::new([]);
+        assert_eq!(d.count(), 0);
+        assert!(d.median().is_nan());
+        assert!(d.stddev().is_nan());
+    }
+
+    #[test]
+    fn test_distributions() {
+        let players = [
+            ProfileStats::from_stat(50.0, 1.5, 100.0),
+            ProfileStats::from_stat(70.0, 2.5, 140.0),
+        ];
+        let d = distributions(&players);
+        assert_eq!(d[&Stat::Apm].median(), 60.0);
+        assert_eq!(d[&Stat::Tr].count(), 0);
+
+        let pct = percentiles(&players[1], &d);
+        assert_eq!(pct[0], (Stat::Apm, 75.0));
+        assert!(pct.iter().all(|(stat, _)| *stat != Stat::Tr));
+    }
+}
@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+#[derive(Debug)]
+pub enum Errors {
+    FailedToBuildClient(reqwest::Error),
+    /// [`crate::TetrioClientBuilder::rate_limit`] was given a rate that isn't above 0.
+    InvalidRateLimit(f64),
+    /// The request never got a response (DNS, connection, timeout...).
+    FailedToSendRequest(reqwest::Error),
+    /// The response body isn't what the models expect.
+    MalformedResponse(serde_json::Error),
+    /// The API answered with `success: false`.
+    Api {
+        status: u16,
+        message: String,
+    },
+    /// HTTP 429, `retry_after` comes from the `Retry-After` header.
+    RateLimited {
+        retry_after: Option<Duration>,
+    },
+    /// HTTP 5xx, ch.tetr.io is having a bad time.
+    ServerError {
+        status: u16,
+        message: Option<String>,
+    },
+    UserNotFound(String),
+    UserBanned(String),
+    AnonymousUser(String),
+    /// The user has no TETRA LEAGUE stats to work with.
+    Unranked(String),
+}
+
+impl Errors {
+    /// How long the API asked us to wait before trying again, if it did.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Errors::RateLimited { retry_after } => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// Whether sending the same request again later could succeed.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Errors::RateLimited { .. } | Errors::ServerError { .. } => true,
+            Errors::FailedToSendRequest(e) => e.is_timeout() || e.is_connect(),
+            _ => false,
+        }
+    }
+}
+
+impl std::error::Error for Errors {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Errors::FailedToBuildClient(e) | Errors::FailedToSendRequest(e) => Some(e),
+            Errors::MalformedResponse(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Errors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Errors::FailedToBuildClient(e) => write!(f, "Failed to build the HTTP client: {e}"),
+            Errors::InvalidRateLimit(r) => {
+                write!(f, "Rate limit must be above 0 requests per second, got {r}")
+            }
+            Errors::FailedToSendRequest(e) => write!(f, "Failed to reach ch.tetr.io: {e}"),
+            Errors::MalformedResponse(e) => {
+                write!(
+                    f,
+                    "ch.tetr.io sent a response osker doesn't understand: {e}"
+                )
+            }
+            Errors::Api { status, message } => {
+                write!(f, "ch.tetr.io said no ({status}): {message}")
+            }
+            Errors::RateLimited {
+                retry_after: Some(d),
+            } => write!(
+                f,
+                "Rate limited by ch.tetr.io, try again in {} seconds",
+                d.as_secs().max(1)
+            ),
+            Errors::RateLimited { retry_after: None } => {
+                f.write_str("Rate limited by ch.tetr.io, try again later")
+            }
+            Errors::ServerError {
+                status,
+                message: Some(m),
+            } => write!(f, "ch.tetr.io is having issues ({status}): {m}"),
+            Errors::ServerError {
+                status,
+                message: None,
+            } => write!(f, "ch.tetr.io is having issues ({status})"),
+            Errors::UserNotFound(u) => write!(f, "User {u} not found"),
+            Errors::UserBanned(u) => write!(f, "User {u} is banned"),
+            Errors::AnonymousUser(u) => write!(f, "User {u} is an anonymous account"),
+            Errors::Unranked(u) => write!(f, "User {u} has no TETRA LEAGUE stats"),
+        }
+    }
+}
@@ -0,0 +1,320 @@
+use std::f64::consts::PI;
+
+use crate::{weights, Metric, PlaystyleTerms, ProfileStats, Stat};
+
+/// One intermediate value of a [`Trace`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub label: String,
+    pub formula: String,
+    pub value: f64,
+}
+
+/// How a stat was calculated for one player, every intermediate value included.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trace {
+    pub stat: Stat,
+    /// The formula in terms of the steps' labels.
+    pub formula: &'static str,
+    pub steps: Vec<Step>,
+    pub result: f64,
+}
+
+#[derive(Default)]
+struct Builder {
+    steps: Vec<Step>,
+}
+
+impl Builder {
+    fn step(&mut self, label: &str, formula: impl Into<String>, value: f64) -> f64 {
+        self.steps.push(Step {
+            label: label.to_string(),
+            formula: formula.into(),
+            value,
+        });
+        value
+    }
+
+    /// A step for another stat, named and described the way the registry has it.
+    fn stat(&mut self, stat: Stat, p: &ProfileStats) -> f64 {
+        // only called for stats every player has
+        self.step(stat.name(), stat.formula(), stat.of(p).unwrap_or(f64::NAN))
+    }
+
+    fn stats(&mut self, stats: &[Stat], p: &ProfileStats) {
+        for stat in stats {
+            self.stat(*stat, p);
+        }
+    }
+}
+
+/// Steps for the stats SR comes from, up to SR Area.
+fn sr_area(b: &mut Builder, p: &ProfileStats) {
+    b.stats(
+        &[
+            Stat::Apm,
+            Stat::Pps,
+            Stat::Vs,
+            Stat::DsSecond,
+            Stat::DsPiece,
+            Stat::App,
+        ],
+        p,
+    );
+    b.step(
+        "SR Area",
+        format!(
+            "PPS × {} + APP × {} + DS/Piece × {}",
+            weights::PPS_SRW,
+            weights::APP_SRW,
+            weights::DS_PIECES_SRW
+        ),
+        p.sr_area(),
+    );
+}
+
+/// Steps for the values the playstyle metrics compare against what's typical for their SR.
+fn playstyle(b: &mut Builder, p: &ProfileStats) -> PlaystyleTerms {
+    sr_area(b, p);
+    b.stats(&[Stat::GarbageEfficiency, Stat::VsApm, Stat::Sr], p);
+    let t = p.playstyle_terms();
+    for (label, formula, value) in [
+        (
+            "APM term",
+            "APM / SR Area / (0.069 × 1.0017^(SR⁵ / 4700) + SR / 360) − 1",
+            t.apm,
+        ),
+        (
+            "PPS term",
+            "PPS / SR Area / (0.0084264 × 2.14^(−2 × (SR / 2.7 + 1.03)) − SR / 5750 + 0.0067) − 1",
+            t.pps,
+        ),
+        (
+            "VS/APM term",
+            "VS/APM / (2.133 − ((SR − 16) / 36)²) − 1",
+            t.vs_apm,
+        ),
+        (
+            "APP term",
+            "APP / (0.1368803292 × 1.0024^(SR⁵ / 2800) + SR / 54) − 1",
+            t.app,
+        ),
+        (
+            "DS/Piece term",
+            "DS/Piece / (0.02136327583 × 14^((SR − 14.75) / 3.9) + SR / 152 + 0.022) − 1",
+            t.ds_pieces,
+        ),
+        (
+            "Garbage Efficiency term",
+            "Garbage Efficiency / (SR / 350 + 0.005948424455 × 3.8^((SR − 6.1) / 4) + 0.006) − 1",
+            t.garbage_efficiency,
+        ),
+    ] {
+        b.step(label, formula, value);
+    }
+    t
+}
+
+/// Explains how `stat` comes out of `player`'s APM, PPS and VS. `None` for stats the player
+/// doesn't have, like TR of a made up player.
+pub fn explain(player: &ProfileStats, stat: Stat) -> Option<Trace> {
+    let result = stat.of(player)?;
+    let p = player;
+    let mut b = Builder::default();
+    let base = [Stat::Apm, Stat::Pps, Stat::Vs];
+    match stat {
+        Stat::Apm | Stat::Pps | Stat::Vs | Stat::Tr | Stat::Glicko | Stat::Rd => {}
+        Stat::App | Stat::DsSecond | Stat::VsApm => b.stats(&base, p),
+        Stat::DsPiece => {
+            b.stats(&base, p);
+            b.stat(Stat::DsSecond, p);
+        }
+        Stat::AppDsPiece => {
+            b.stats(&base, p);
+            b.stats(&[Stat::App, Stat::DsSecond, Stat::DsPiece], p);
+        }
+        Stat::GarbageEfficiency => {
+            b.stats(&base, p);
+            b.stats(&[Stat::App, Stat::DsSecond], p);
+        }
+        Stat::CheeseIndex => {
+            b.stats(&base, p);
+            b.stat(Stat::DsSecond, p);
+            let ds_piece = b.stat(Stat::DsPiece, p);
+            let vs_apm = b.stat(Stat::VsApm, p);
+            let app = b.stat(Stat::App, p);
+            b.step("DS/Piece part", "DS/Piece × 150", ds_piece * 150.0);
+            b.step("VS/APM part", "(VS/APM − 2) × 50", (vs_apm - 2.0) * 50.0);
+            b.step("APP part", "(0.6 − APP) × 125", (0.6 - app) * 125.0);
+        }
+        Stat::WeightedApp => {
+            b.stats(&base, p);
+            b.stats(&[Stat::App, Stat::DsSecond, Stat::DsPiece, Stat::VsApm], p);
+            let ci = b.stat(Stat::CheeseIndex, p);
+            let angle = b.step(
+                "Angle",
+                "Cheese Index / −30 + 1, in degrees",
+                ci / -30.0 + 1.0,
+            );
+            b.step(
+                "Cheese penalty",
+                "5 × tan(Angle°)",
+                5.0 * (angle * PI / 180.0).tan(),
+            );
+        }
+        Stat::Area => {
+            let weighted = [
+                (Stat::Apm, weights::APM_WEIGHT as f64),
+                (Stat::Pps, weights::PPS_WEIGHT as f64),
+                (Stat::Vs, weights::VS_WEIGHT),
+                (Stat::App, weights::APP_WEIGHT as f64),
+                (Stat::DsSecond, weights::DS_SECONDS_WEIGHT as f64),
+                (Stat::DsPiece, weights::DS_PIECES_WEIGHT as f64),
+                (
+                    Stat::GarbageEfficiency,
+                    weights::GARBAGE_EFFICIENCY_WEIGHT as f64,
+                ),
+            ];
+            let values: Vec<f64> = weighted.iter().map(|(part, _)| b.stat(*part, p)).collect();
+            for ((part, weight), value) in weighted.iter().zip(values) {
+                b.step(
+                    &format!("{part} part"),
+                    format!("{part} × {weight}"),
+                    value * weight,
+                );
+            }
+        }
+        Stat::Sr => sr_area(&mut b, p),
+        Stat::EstimatedTr | Stat::EstimatedTrAccuracy => {
+            b.stats(&base, p);
+            let app = b.stat(Stat::App, p);
+            b.stat(Stat::DsSecond, p);
+            let ds_piece = b.stat(Stat::DsPiece, p);
+            let vs_apm = b.stat(Stat::VsApm, p);
+            b.step(
+                "x",
+                "PPS × (150 + (VS/APM − 1.66) × 35) + APP × 290 + DS/Piece × 700",
+                p.pps as f64 * (150.0 + ((vs_apm - 1.66) * 35.0)) + app * 290.0 + ds_piece * 700.0,
+            );
+            b.step(
+                "Estimated Glicko",
+                "0.000013 × x³ − 0.0196 × x² + 12.645 × x − 1005.4",
+                p.estimated_glicko(),
+            );
+            if stat == Stat::EstimatedTrAccuracy {
+                b.stats(&[Stat::EstimatedTr, Stat::Tr], p);
+            }
+        }
+        Stat::Opener => {
+            let t = playstyle(&mut b, p);
+            for (label, term, weight) in [
+                ("APM", t.apm, 1.0),
+                ("PPS", t.pps, 0.75),
+                ("VS/APM", t.vs_apm, -10.0),
+                ("APP", t.app, 0.75),
+                ("DS/Piece", t.ds_pieces, -0.25),
+            ] {
+                contribution(&mut b, label, term, weight);
+            }
+        }
+        Stat::Plonk => {
+            let t = playstyle(&mut b, p);
+            for (label, term, weight) in [
+                ("Garbage Efficiency", t.garbage_efficiency, 1.0),
+                ("APP", t.app, 1.0),
+                ("DS/Piece", t.ds_pieces, 0.75),
+                ("PPS", t.pps, -1.0),
+            ] {
+                contribution(&mut b, label, term, weight);
+            }
+        }
+        Stat::Stride => {
+            let t = playstyle(&mut b, p);
+            for (label, term, weight) in [
+                ("APM", t.apm, -0.25),
+                ("PPS", t.pps, 1.0),
+                ("APP", t.app, -2.0),
+                ("DS/Piece", t.ds_pieces, -0.5),
+            ] {
+                contribution(&mut b, label, term, weight);
+            }
+        }
+        Stat::InfiniteDownstack => {
+            let t = playstyle(&mut b, p);
+            for (label, term, weight) in [
+                ("DS/Piece", t.ds_pieces, 1.0),
+                ("APP", t.app, -0.75),
+                ("APM", t.apm, 0.5),
+                ("VS/APM", t.vs_apm, 1.5),
+                ("PPS", t.pps, 0.5),
+            ] {
+                contribution(&mut b, label, term, weight);
+            }
+        }
+    }
+    Some(Trace {
+        stat,
+        formula: stat.formula(),
+        steps: b.steps,
+        result,
+    })
+}
+
+/// A playstyle term times its weight, what it adds to the metric before scaling.
+fn contribution(b: &mut Builder, label: &str, term: f64, weight: f64) {
+    b.step(
+        &format!("{label} contribution"),
+        format!("{label} term × {weight}"),
+        term * weight,
+    );
+}
+
+#[cfg(all(test, feature = "test"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_stat_explained() {
+        let player = ProfileStats {
+            tr: Some(20000.0),
+            glicko: Some(2000.0),
+            rd: Some(60.0),
+            is_real: true,
+            ..ProfileStats::from_stat(80.0, 2.3, 170.0)
+        };
+        for stat in Stat::ALL {
+            let trace = explain(&player, *stat).unwrap();
+            assert_eq!(trace.result, stat.of(&player).unwrap());
+        }
+        assert!(explain(&ProfileStats::from_stat(80.0, 2.3, 170.0), Stat::Tr).is_none());
+    }
+
+    /// The contributions and parts should add up to the result, so the trace can't drift from
+    /// the real formulas.
+    #[test]
+    fn test_steps_add_up() {
+        let player = ProfileStats::from_stat(80.0, 2.3, 170.0);
+        let sum = |trace: &Trace, suffix: &str| -> f64 {
+            trace
+                .steps
+                .iter()
+                .filter(|s| s.label.ends_with(suffix))
+                .map(|s| s.value)
+                .sum()
+        };
+
+        let opener = explain(&player, Stat::Opener).unwrap();
+        assert!((sum(&opener, " contribution") / 3.5 + 0.5 - opener.result).abs() < 1e-9);
+        let plonk = explain(&player, Stat::Plonk).unwrap();
+        assert!((sum(&plonk, " contribution") / 2.73 + 0.5 - plonk.result).abs() < 1e-4);
+        let stride = explain(&player, Stat::Stride).unwrap();
+        assert!((sum(&stride, " contribution") * 0.79 + 0.5 - stride.result).abs() < 1e-4);
+        let inf = explain(&player, Stat::InfiniteDownstack).unwrap();
+        assert!((sum(&inf, " contribution") * 0.9 + 0.5 - inf.result).abs() < 1e-4);
+
+        let area = explain(&player, Stat::Area).unwrap();
+        assert!((sum(&area, " part") - area.result).abs() < 1e-9);
+        let ci = explain(&player, Stat::CheeseIndex).unwrap();
+        assert!((sum(&ci, " part") - ci.result).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,327 @@
+use std::future::Future;
+
+use futures_util::{stream, Stream, TryStreamExt};
+
+use crate::{
+    models::{Leaderboard, LeaderboardEntry, Prisecter},
+    Errors, ProfileStats, TetrioClient,
+};
+
+/// Largest page ch.tetr.io hands out for `users/by/league`.
+pub const MAX_PAGE_SIZE: u32 = 100;
+
+/// Which part of the TETRA LEAGUE leaderboard to crawl.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeaderboardQuery {
+    /// Entries per page, clamped to `1..=100`.
+    pub limit: u32,
+    /// Stop after this many pages, `None` crawls until the last page.
+    pub max_pages: Option<u32>,
+    /// Resume after this cursor, usually [`LeaderboardPage::cursor`] of a saved page.
+    pub after: Option<Prisecter>,
+    /// Only players from this country (ISO 3166-1 code, like `US`).
+    pub country: Option<String>,
+}
+
+impl Default for LeaderboardQuery {
+    fn default() -> Self {
+        Self {
+            limit: MAX_PAGE_SIZE,
+            max_pages: None,
+            after: None,
+            country: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LeaderboardPage {
+    /// 1-based index of this page within the crawl.
+    pub number: u32,
+    pub entries: Vec<ProfileStats>,
+    /// Cursor of the last entry, pass it as [`LeaderboardQuery::after`] to continue from here.
+    pub cursor: Option<Prisecter>,
+    /// Whether this is the last page of the leaderboard.
+    pub last: bool,
+}
+
+/// Query parameters of one `users/by/league` request.
+type Params = Vec<(&'static str, String)>;
+
+struct State<F> {
+    fetch: F,
+    query: LeaderboardQuery,
+    pages: u32,
+    done: bool,
+}
+
+impl TetrioClient {
+    /// Lazily crawls the TETRA LEAGUE leaderboard, one request per page.
+    pub fn leaderboard_stream(
+        &self,
+        limit: u32,
+        max_pages: Option<u32>,
+    ) -> impl Stream<Item = Result<ProfileStats, Errors>> + Send + 'static {
+        self.leaderboard_stream_with(LeaderboardQuery {
+            limit,
+            max_pages,
+            ..Default::default()
+        })
+    }
+
+    /// Like [`Self::leaderboard_stream`] with a cursor to resume from and a country filter.
+    pub fn leaderboard_stream_with(
+        &self,
+        query: LeaderboardQuery,
+    ) -> impl Stream<Item = Result<ProfileStats, Errors>> + Send + 'static {
+        entries(self.leaderboard_pages(query))
+    }
+
+    /// The pages behind [`Self::leaderboard_stream_with`], for callers that need the cursors.
+    pub fn leaderboard_pages(
+        &self,
+        query: LeaderboardQuery,
+    ) -> impl Stream<Item = Result<LeaderboardPage, Errors>> + Send + 'static {
+        let client = self.clone();
+        paginate(query, move |params: Params| {
+            let client = client.clone();
+            async move {
+                let params: Vec<(&str, &str)> =
+                    params.iter().map(|(k, v)| (*k, v.as_str())).collect();
+                Ok(client
+                    .get_response::<Leaderboard>("users/by/league", &params)
+                    .await?
+                    .into_data()?
+                    .entries)
+            }
+        })
+    }
+}
+
+/// Every entry of `pages`, in order.
+fn entries(
+    pages: impl Stream<Item = Result<LeaderboardPage, Errors>> + Send + 'static,
+) -> impl Stream<Item = Result<ProfileStats, Errors>> + Send + 'static {
+    pages
+        .map_ok(|page| stream::iter(page.entries.into_iter().map(Ok)))
+        .try_flatten()
+}
+
+/// Pages of `query`, each one's entries fetched by `fetch` from its request's query parameters.
+/// Kept apart from the client so the paging can be tested without ch.tetr.io.
+fn paginate<F, Fut>(
+    query: LeaderboardQuery,
+    fetch: F,
+) -> impl Stream<Item = Result<LeaderboardPage, Errors>> + Send + 'static
+where
+    F: FnMut(Params) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<Vec<LeaderboardEntry>, Errors>> + Send,
+{
+    let state = State {
+        fetch,
+        query: LeaderboardQuery {
+            limit: query.limit.clamp(1, MAX_PAGE_SIZE),
+            ..query
+        },
+        pages: 0,
+        done: false,
+    };
+    stream::try_unfold(state, |mut state| async move {
+        if state.done || state.query.max_pages.is_some_and(|m| state.pages >= m) {
+            return Ok(None);
+        }
+        let mut params = vec![("limit", state.query.limit.to_string())];
+        if let Some(a) = state.query.after {
+            params.push(("after", a.to_string()));
+        }
+        if let Some(c) = &state.query.country {
+            params.push(("country", c.clone()));
+        }
+        let entries = (state.fetch)(params).await?;
+
+        state.pages += 1;
+        // a short page means there's nothing after it
+        state.done = (entries.len() as u32) < state.query.limit;
+        if let Some(last) = entries.last() {
+            state.query.after = Some(last.p);
+        }
+        let page = LeaderboardPage {
+            number: state.pages,
+            entries: entries.iter().map(ProfileStats::from).collect(),
+            cursor: state.query.after,
+            last: state.done,
+        };
+        Ok(Some((page, state)))
+    })
+}
+
+#[cfg(all(test, feature = "test"))]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use futures_util::TryStreamExt;
+
+    use super::*;
+    use crate::{models::LeaderboardLeague, Ranks};
+
+    fn entry(i: u32) -> LeaderboardEntry {
+        LeaderboardEntry {
+            id: format!("id{i}"),
+            username: format!("player{i}"),
+            role: "user".to_string(),
+            country: None,
+            league: LeaderboardLeague {
+                gamesplayed: 100,
+                gameswon: 50,
+                rank: Ranks::A,
+                tr: 20000.0 - i as f64,
+                glicko: 2000.0,
+                rd: 60.0,
+                apm: 60.0,
+                pps: 2.0,
+                vs: 120.0,
+            },
+            p: Prisecter {
+                pri: 20000.0 - i as f64,
+                sec: 0.0,
+                ter: 0.0,
+            },
+        }
+    }
+
+    /// A fake leaderboard of `total` players that records every request's parameters.
+    fn leaderboard(
+        total: u32,
+        requests: Arc<Mutex<Vec<Params>>>,
+    ) -> impl FnMut(Params) -> std::future::Ready<Result<Vec<LeaderboardEntry>, Errors>> {
+        move |params: Params| {
+            let get = |key| {
+                params
+                    .iter()
+                    .find(|(k, _)| *k == key)
+                    .map(|(_, v)| v.clone())
+            };
+            let limit: u32 = get("limit").unwrap().parse().unwrap();
+            // the cursor's `pri` is 20000 - index of the last player seen
+            let start = get("after")
+                .map(|a| 20000 - a.parse::<Prisecter>().unwrap().pri as u32 + 1)
+                .unwrap_or(0);
+            requests.lock().unwrap().push(params);
+            std::future::ready(Ok((start..total.min(start + limit)).map(entry).collect()))
+        }
+    }
+
+    fn query(limit: u32) -> LeaderboardQuery {
+        LeaderboardQuery {
+            limit,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stops_on_short_page() {
+        let requests = Arc::new(Mutex::new(vec![]));
+        let pages: Vec<LeaderboardPage> = paginate(query(10), leaderboard(25, requests.clone()))
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(pages.len(), 3);
+        assert_eq!(pages[2].entries.len(), 5);
+        assert!(pages[2].last && !pages[1].last);
+        assert_eq!(requests.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_max_pages() {
+        let requests = Arc::new(Mutex::new(vec![]));
+        let q = LeaderboardQuery {
+            max_pages: Some(2),
+            ..query(10)
+        };
+        let players: Vec<ProfileStats> = entries(paginate(q, leaderboard(100, requests.clone())))
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(players.len(), 20);
+        assert_eq!(requests.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_cursor_chaining() {
+        let requests = Arc::new(Mutex::new(vec![]));
+        let pages: Vec<LeaderboardPage> = paginate(query(10), leaderboard(30, requests.clone()))
+            .try_collect()
+            .await
+            .unwrap();
+        let requests = requests.lock().unwrap().clone();
+        assert!(requests[0].iter().all(|(k, _)| *k != "after"));
+        for (page, next) in pages.iter().zip(&requests[1..]) {
+            let after = next.iter().find(|(k, _)| *k == "after").unwrap();
+            assert_eq!(after.1, page.cursor.unwrap().to_string());
+        }
+        let names: Vec<String> = pages
+            .iter()
+            .flat_map(|p| &p.entries)
+            .map(|p| p.name.clone().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            (0..30).map(|i| format!("player{i}")).collect::<Vec<_>>()
+        );
+
+        // resuming from a saved cursor picks up right after it
+        let resumed: Vec<ProfileStats> = entries(paginate(
+            LeaderboardQuery {
+                after: pages[0].cursor,
+                ..query(10)
+            },
+            leaderboard(30, Arc::new(Mutex::new(vec![]))),
+        ))
+        .try_collect()
+        .await
+        .unwrap();
+        assert_eq!(resumed[0].name.as_deref(), Some("player10"));
+        assert_eq!(resumed.len(), 20);
+    }
+
+    #[tokio::test]
+    async fn test_country_and_limit_params() {
+        let requests = Arc::new(Mutex::new(vec![]));
+        let q = LeaderboardQuery {
+            limit: 500,
+            country: Some("US".to_string()),
+            max_pages: Some(1),
+            ..Default::default()
+        };
+        paginate(q, leaderboard(10, requests.clone()))
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        let requests = requests.lock().unwrap();
+        assert!(requests[0].contains(&("country", "US".to_string())));
+        assert!(requests[0].contains(&("limit", MAX_PAGE_SIZE.to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_error_mid_stream() {
+        let mut calls = 0;
+        let fetch = move |_: Params| {
+            calls += 1;
+            std::future::ready(match calls {
+                1 => Ok((0..10).map(entry).collect()),
+                _ => Err(Errors::ServerError {
+                    status: 503,
+                    message: None,
+                }),
+            })
+        };
+        let results: Vec<Result<ProfileStats, Errors>> =
+            futures_util::StreamExt::collect(entries(paginate(query(10), fetch))).await;
+        assert_eq!(results.len(), 11);
+        assert!(results[..10].iter().all(Result::is_ok));
+        assert!(matches!(
+            results[10],
+            Err(Errors::ServerError { status: 503, .. })
+        ));
+    }
+}
@@ -1,10 +1,35 @@
-use std::str::FromStr;
-
-use reqwest::ClientBuilder;
-
-pub const API: &'static str = "https://ch.tetr.io/api/";
-
-#[derive(Debug, Clone, PartialEq)]
+mod cache;
+mod classify;
+mod client;
+mod cutoffs;
+mod distribution;
+mod errors;
+mod explain;
+mod leaderboard;
+mod metric;
+pub mod models;
+mod ratelimit;
+mod sensitivity;
+mod solver;
+mod stat;
+
+pub use cache::Fetched;
+pub use classify::{classify, RankMatch, CLASSIFIER_STATS};
+pub use client::{TetrioClient, TetrioClientBuilder, DEFAULT_USER_AGENT};
+pub use cutoffs::{Cutoffs, Prediction, RANK_PERCENTILES};
+pub use distribution::{distributions, percentiles, Distribution};
+pub use errors::Errors;
+pub use explain::{explain, Step, Trace};
+pub use leaderboard::{LeaderboardPage, LeaderboardQuery, MAX_PAGE_SIZE};
+pub use metric::Metric;
+pub use ratelimit::Priority;
+pub use sensitivity::{partials, BaseStat, Partial};
+pub use solver::{apply, solve, Direction, Solution};
+pub use stat::Stat;
+
+pub const API: &str = "https://ch.tetr.io/api/";
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct ProfileStats {
     pub apm: f32,
     pub pps: f32,
@@ -12,6 +37,8 @@ pub struct ProfileStats {
     pub rank: Option<Ranks>,
     pub tr: Option<f64>,
     pub name: Option<String>,
+    /// ch.tetr.io user ID.
+    pub id: Option<String>,
     pub pfp: Option<String>,
     pub glicko: Option<f64>,
     pub rd: Option<f64>,
@@ -36,6 +63,11 @@ macro_rules! enum_from_string {
             }
         }
 
+        impl $name {
+            /// Every variant, in declaration order.
+            pub const VARIANTS: &'static [$name] = &[$($name::$variant),*];
+        }
+
         impl std::fmt::Display for $name {
             fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
                 match self {
@@ -70,6 +102,16 @@ enum_from_string!(Ranks {
     Z
 });
 
+impl Ranks {
+    /// How ranks are written in game, like `X+`, `S-` or `ALL`.
+    pub fn symbol(&self) -> String {
+        self.to_string()
+            .to_uppercase()
+            .replace("PLUS", "+")
+            .replace("MINUS", "-")
+    }
+}
+
 pub mod weights {
     // Weights for area stat
     pub const APM_WEIGHT: usize = 1;
@@ -93,75 +135,26 @@ pub mod weights {
     pub const GARBAGE_EFFICIENCY_SRW: usize = 0;
 }
 
-#[derive(Debug)]
-pub enum Errors {
-    FailedToSendRequest(reqwest::Error),
-    UserNotFound,
-}
-
-impl std::error::Error for Errors {}
-impl std::fmt::Display for Errors {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(&format!("{:#?}", self))
+impl ProfileStats {
+    pub async fn from_username(client: &TetrioClient, username: &str) -> Result<Self, Errors> {
+        client.profile(username).await.map(|f| f.data)
     }
-}
 
-impl ProfileStats {
-    pub async fn from_username<'b>(username: &'b str) -> Result<Self, Errors> {
-        let client = ClientBuilder::new()
-            .user_agent("tlns-tetrio-calcs")
-            .build()
-            .map_err(Errors::FailedToSendRequest)?;
-        let user_api = API.to_string() + "users/" + username;
-        let rank_summary = user_api.clone() + "/summaries/league";
-        let user_info: serde_json::Value = client
-            .get(user_api)
-            .send()
-            .await
-            .map_err(Errors::FailedToSendRequest)?
-            .error_for_status()
-            .map_err(Errors::FailedToSendRequest)?
-            .json::<serde_json::Value>()
-            .await
-            .map_err(Errors::FailedToSendRequest)?;
-        let rank_info: serde_json::Value = client
-            .get(rank_summary)
-            .send()
-            .await
-            .map_err(Errors::FailedToSendRequest)?
-            .error_for_status()
-            .map_err(Errors::FailedToSendRequest)?
-            .json::<serde_json::Value>()
-            .await
-            .map_err(Errors::FailedToSendRequest)?;
-        if user_info["success"].as_bool().unwrap_or(false) == false {
-            return Err(Errors::UserNotFound);
-        }
-        let user_info = user_info["data"].clone();
-        let rank_info = rank_info["data"].clone();
-        Ok(Self {
-            apm: rank_info["apm"].as_f64().unwrap_or(0.0) as f32,
-            pps: rank_info["pps"].as_f64().unwrap_or(0.0) as f32,
-            vs: rank_info["vs"].as_f64().unwrap_or(0.0) as f32,
-            rank: Some(
-                Ranks::from_str(&rank_info["rank"].as_str().unwrap_or("Z").to_uppercase()).unwrap(),
-            ),
-            tr: Some(rank_info["tr"].as_f64().unwrap_or(0.0)),
-            name: Some(user_info["username"].as_str().unwrap().to_string()),
-            pfp: Some(
-                "https://tetr.io/user-content/avatars/".to_string()
-                    + user_info["_id"].as_str().unwrap()
-                    + ".jpg?rv="
-                    + user_info["avatar_revision"]
-                        .as_u64()
-                        .unwrap_or(0)
-                        .to_string()
-                        .as_str(),
-            ),
-            rd: Some(rank_info["rd"].as_f64().unwrap_or(0.0)),
-            glicko: Some(rank_info["glicko"].as_f64().unwrap_or(0.0)),
+    /// Builds a profile from a user and their TETRA LEAGUE summary.
+    pub fn from_league(user: &models::User, league: &models::LeagueSummary) -> Self {
+        Self {
+            apm: league.apm.unwrap_or(0.0) as f32,
+            pps: league.pps.unwrap_or(0.0) as f32,
+            vs: league.vs.unwrap_or(0.0) as f32,
+            rank: Some(league.rank),
+            tr: Some(league.tr.unwrap_or(0.0)),
+            name: Some(user.username.clone()),
+            id: Some(user.id.clone()),
+            pfp: Some(user.avatar_url()),
+            glicko: Some(league.glicko.unwrap_or(0.0)),
+            rd: Some(league.rd.unwrap_or(0.0)),
             is_real: true,
-        })
+        }
     }
 
     pub fn from_stat(apm: f32, pps: f32, vs: f32) -> Self {
@@ -172,6 +165,7 @@ impl ProfileStats {
             rank: None,
             tr: None,
             name: None,
+            id: None,
             pfp: None,
             glicko: Some(60.0), // statPlayer = new Player("EXAMPLE", name[0], name[1], name[2], 0, 0, 60, null) but why?
             rd: None,
@@ -215,7 +209,7 @@ impl ProfileStats {
     pub fn area(&self) -> f64 {
         self.apm as f64
             + self.pps as f64 * weights::PPS_WEIGHT as f64
-            + self.vs as f64 * weights::VS_WEIGHT as f64
+            + self.vs as f64 * weights::VS_WEIGHT
             + self.app() * weights::APP_WEIGHT as f64
             + self.ds_seconds() * weights::DS_SECONDS_WEIGHT as f64
             + self.ds_pieces() * weights::DS_PIECES_WEIGHT as f64
@@ -256,114 +250,55 @@ impl ProfileStats {
         }
     }
 
+    /// How far each stat is from what's typical at the player's SR, `0.0` being typical. The
+    /// playstyle metrics are weighted sums of these.
+    pub fn playstyle_terms(&self) -> PlaystyleTerms {
+        let sr_area = self.sr_area();
+        let sr = self.sr();
+        PlaystyleTerms {
+            apm: (self.apm as f64 / sr_area)
+                / ((0.069 * 1.0017_f64.powf((sr.powi(5)) / 4700.0)) + sr / 360.0)
+                - 1.0,
+            pps: (self.pps as f64 / sr_area)
+                / (0.0084264 * (2.14_f64.powf(-2.0 * (sr / 2.7 + 1.03))) - sr / 5750.0 + 0.0067)
+                - 1.0,
+            vs_apm: self.vs_apm() / (-(((sr - 16.0) / 36.0).powi(2)) + 2.133) - 1.0,
+            app: self.app() / (0.1368803292 * 1.0024_f64.powf((sr.powi(5)) / 2800.0) + sr / 54.0)
+                - 1.0,
+            ds_pieces: self.ds_pieces()
+                / (0.02136327583 * (14.0_f64.powf((sr - 14.75) / 3.9)) + sr / 152.0 + 0.022)
+                - 1.0,
+            garbage_efficiency: self.garbage_efficiency()
+                / (sr / 350.0 + 0.005948424455 * 3.8_f64.powf((sr - 6.1) / 4.0) + 0.006)
+                - 1.0,
+        }
+    }
+
     #[inline(always)]
     pub fn opener(&self) -> f64 {
-        ((((self.apm as f64 / self.sr_area())
-            / ((0.069 * 1.0017_f64.powf((self.sr().powi(5)) / 4700.0)) + self.sr() / 360.0)
-            - 1.0)
-            + (((self.pps as f64 / self.sr_area())
-                / (0.0084264 * (2.14_f64.powf(-2.0 * (self.sr() / 2.7 + 1.03)))
-                    - self.sr() / 5750.0
-                    + 0.0067)
-                - 1.0)
-                * 0.75)
-            + ((self.vs_apm() / (-(((self.sr() - 16.0) / 36.0).powi(2)) + 2.133) - 1.0) * -10.0)
-            + ((self.app()
-                / (0.1368803292 * 1.0024_f64.powf((self.sr().powi(5)) / 2800.0)
-                    + self.sr() / 54.0)
-                - 1.0)
-                * 0.75)
-            + ((self.ds_pieces()
-                / (0.02136327583 * (14.0_f64.powf((self.sr() - 14.75) / 3.9))
-                    + self.sr() / 152.0
-                    + 0.022)
-                - 1.0)
-                * -0.25))
-            / 3.5)
-            + 0.5
+        let t = self.playstyle_terms();
+        ((t.apm + t.pps * 0.75 + t.vs_apm * -10.0 + t.app * 0.75 + t.ds_pieces * -0.25) / 3.5) + 0.5
     }
 
     #[inline(always)]
     pub fn plonk(&self) -> f64 {
-        let x = (((self.garbage_efficiency()
-            / (self.sr() / 350.0
-                + 0.005948424455 * 3.8_f64.powf((self.sr() - 6.1) / 4.0)
-                + 0.006)
-            - 1.0)
-            + (self.app()
-                / (0.1368803292 * 1.0024_f64.powf((self.sr().powi(5)) / 2800.0)
-                    + self.sr() / 54.0)
-                - 1.0)
-            + ((self.ds_pieces()
-                / (0.02136327583 * (14.0_f64.powf((self.sr() - 14.75) / 3.9))
-                    + self.sr() / 152.0
-                    + 0.022)
-                - 1.0)
-                * 0.75)
-            + (((self.pps as f64 / self.sr_area())
-                / (0.0084264 * (2.14_f64.powf(-2.0 * (self.sr() / 2.7 + 1.03)))
-                    - self.sr() / 5750.0
-                    + 0.0067)
-                - 1.0)
-                * -1.0))
-            / 2.73)
-            + 0.5;
+        let t = self.playstyle_terms();
+        let x = ((t.garbage_efficiency + t.app + t.ds_pieces * 0.75 - t.pps) / 2.73) + 0.5;
         truncate(x, 4)
     }
 
     #[inline(always)]
     pub fn stride(&self) -> f64 {
-        let x = (((((self.apm as f64 / self.sr_area())
-            / ((0.069 * 1.0017_f64.powf((self.sr().powi(5)) / 4700.0)) + self.sr() / 360.0)
-            - 1.0)
-            * -0.25)
-            + ((self.pps as f64 / self.sr_area())
-                / (0.0084264 * (2.14_f64.powf(-2.0 * (self.sr() / 2.7 + 1.03)))
-                    - self.sr() / 5750.0
-                    + 0.0067)
-                - 1.0)
-            + ((self.app()
-                / (0.1368803292 * 1.0024_f64.powf((self.sr().powi(5)) / 2800.0)
-                    + self.sr() / 54.0)
-                - 1.0)
-                * -2.0)
-            + ((self.ds_pieces()
-                / (0.02136327583 * (14.0_f64.powf((self.sr() - 14.75) / 3.9))
-                    + self.sr() / 152.0
-                    + 0.022)
-                - 1.0)
-                * -0.5))
-            * 0.79)
-            + 0.5;
+        let t = self.playstyle_terms();
+        let x = ((t.apm * -0.25 + t.pps + t.app * -2.0 + t.ds_pieces * -0.5) * 0.79) + 0.5;
         truncate(x, 4)
     }
 
     #[inline(always)]
     pub fn infinite_downstack(&self) -> f64 {
-        let x = (((self.ds_pieces()
-            / (0.02136327583 * (14.0_f64.powf((self.sr() - 14.75) / 3.9))
-                + self.sr() / 152.0
-                + 0.022)
-            - 1.0)
-            + ((self.app()
-                / (0.1368803292 * 1.0024_f64.powf((self.sr().powi(5)) / 2800.0)
-                    + self.sr() / 54.0)
-                - 1.0)
-                * -0.75)
-            + (((self.apm as f64 / self.sr_area())
-                / ((0.069 * 1.0017_f64.powf((self.sr().powi(5)) / 4700.0)) + self.sr() / 360.0)
-                - 1.0)
-                * 0.5)
-            + ((self.vs_apm() / (-(((self.sr() - 16.0) / 36.0).powi(2)) + 2.133) - 1.0) * 1.5)
-            + (((self.pps as f64 / self.sr_area())
-                / (0.0084264 * (2.14_f64.powf(-2.0 * (self.sr() / 2.7 + 1.03)))
-                    - self.sr() / 5750.0
-                    + 0.0067)
-                - 1.0)
-                * 0.5))
-            * 0.9)
+        let t = self.playstyle_terms();
+        let x = ((t.ds_pieces + t.app * -0.75 + t.apm * 0.5 + t.vs_apm * 1.5 + t.pps * 0.5) * 0.9)
             + 0.5;
-
         truncate(x, 4)
     }
 
@@ -406,6 +341,35 @@ impl ProfileStats {
     }
 }
 
+/// See [`ProfileStats::playstyle_terms`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlaystyleTerms {
+    pub apm: f64,
+    pub pps: f64,
+    pub vs_apm: f64,
+    pub app: f64,
+    pub ds_pieces: f64,
+    pub garbage_efficiency: f64,
+}
+
+impl From<&models::LeaderboardEntry> for ProfileStats {
+    fn from(entry: &models::LeaderboardEntry) -> Self {
+        Self {
+            apm: entry.league.apm as f32,
+            pps: entry.league.pps as f32,
+            vs: entry.league.vs as f32,
+            rank: Some(entry.league.rank),
+            tr: Some(entry.league.tr),
+            name: Some(entry.username.clone()),
+            id: Some(entry.id.clone()),
+            pfp: Some(models::avatar_url(&entry.id, None)),
+            glicko: Some(entry.league.glicko),
+            rd: Some(entry.league.rd),
+            is_real: true,
+        }
+    }
+}
+
 #[cfg(all(test, feature = "test"))]
 mod tests {
     use once_cell::sync::OnceCell;
@@ -419,12 +383,23 @@ mod tests {
         rank: Some(Ranks::U),
         tr: Some(23684.48),
         name: None,
+        id: None,
         pfp: None,
         glicko: Some(2257.86),
         rd: Some(66.04),
         is_real: true,
     });
 
+    #[test]
+    fn test_rank_symbol() {
+        assert_eq!(Ranks::XPlus.symbol(), "X+");
+        assert_eq!(Ranks::SMinus.symbol(), "S-");
+        assert_eq!(Ranks::ALL.symbol(), "ALL");
+        for rank in Ranks::VARIANTS {
+            assert_eq!(rank.symbol().parse::<Ranks>(), Ok(*rank));
+        }
+    }
+
     #[test]
     fn test_ds_pieces() {
         let a = DATA.wait();
@@ -532,7 +507,8 @@ mod tests {
 
     #[tokio::test]
     async fn test_fetch_pfp() {
-        let e = ProfileStats::from_username("timelessnesses")
+        let client = TetrioClient::new().expect("Failed to build client");
+        let e = ProfileStats::from_username(&client, "timelessnesses")
             .await
             .expect("Failed to fetch profile");
         eprintln!("{:#?}", e);
@@ -0,0 +1,47 @@
+use crate::ProfileStats;
+
+/// A number about a player and everything needed to show it, so tables, embeds and charts can
+/// take a list of metrics instead of naming each one. [`crate::Stat`] is the registry of all of
+/// them.
+pub trait Metric {
+    fn name(&self) -> &'static str;
+
+    /// Shorter name for table rows.
+    fn label(&self) -> &'static str;
+
+    /// How the metric is calculated, in terms of other metrics' names.
+    fn formula(&self) -> &'static str;
+
+    /// Unit shown after the value, empty when there's none.
+    fn unit(&self) -> &'static str;
+
+    /// Decimal places the metric is shown with.
+    fn precision(&self) -> usize;
+
+    /// What the value is multiplied by so it fits on the radar chart, `None` when it isn't on it.
+    fn radar_weight(&self) -> Option<f64>;
+
+    /// Whether a higher value is better, `None` for metrics where neither is.
+    fn higher_is_better(&self) -> Option<bool>;
+
+    /// The metric's value for `player`, `None` when the player doesn't have it.
+    fn of(&self, player: &ProfileStats) -> Option<f64>;
+
+    /// Name of the metric's radar chart axis.
+    fn radar_label(&self) -> &'static str {
+        self.name()
+    }
+
+    /// The value scaled by [`Metric::radar_weight`].
+    fn radar_value(&self, player: &ProfileStats) -> Option<f64> {
+        Some(self.of(player)? * self.radar_weight()?)
+    }
+
+    /// `value` with the metric's precision and unit.
+    fn display(&self, value: f64) -> String {
+        match self.unit() {
+            "" => format!("{value:.*}", self.precision()),
+            unit => format!("{value:.*} {unit}", self.precision()),
+        }
+    }
+}
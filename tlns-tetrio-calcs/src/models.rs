@@ -0,0 +1,244 @@
+//! Typed models for ch.tetr.io responses.
+//!
+//! Fields the bot relies on are required, so a schema change on ch.tetr.io surfaces as a parse
+//! error instead of a panic somewhere down the line.
+
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{Errors, Ranks};
+
+/// The `success/error/cache/data` envelope every ch.tetr.io response is wrapped in.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Response<T> {
+    pub success: bool,
+    pub error: Option<ApiError>,
+    pub cache: Option<CacheInfo>,
+    pub data: Option<T>,
+}
+
+impl<T> Response<T> {
+    /// Takes the `data` out of a successful response.
+    pub fn into_data(self) -> Result<T, Errors> {
+        self.data.ok_or_else(|| {
+            Errors::MalformedResponse(<serde_json::Error as serde::de::Error>::missing_field(
+                "data",
+            ))
+        })
+    }
+}
+
+/// Error returned by the API when `success` is false.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum ApiError {
+    Detailed { msg: String },
+    Message(String),
+}
+
+impl ApiError {
+    pub fn message(&self) -> &str {
+        match self {
+            ApiError::Detailed { msg } => msg,
+            ApiError::Message(msg) => msg,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct CacheInfo {
+    pub status: CacheStatus,
+    /// Unix timestamp in milliseconds.
+    pub cached_at: u64,
+    /// Unix timestamp in milliseconds.
+    pub cached_until: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheStatus {
+    Hit,
+    Miss,
+    Awaited,
+}
+
+/// `GET users/:user`
+#[derive(Debug, Clone, Deserialize)]
+pub struct User {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub username: String,
+    pub role: String,
+    pub avatar_revision: Option<u64>,
+    pub country: Option<String>,
+}
+
+impl User {
+    pub fn avatar_url(&self) -> String {
+        avatar_url(&self.id, self.avatar_revision)
+    }
+}
+
+/// `GET users/:user/summaries/league`
+///
+/// Stats are optional because users that never played TETRA LEAGUE have none.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LeagueSummary {
+    pub gamesplayed: u64,
+    pub gameswon: u64,
+    pub rank: Ranks,
+    pub tr: Option<f64>,
+    pub glicko: Option<f64>,
+    pub rd: Option<f64>,
+    pub apm: Option<f64>,
+    pub pps: Option<f64>,
+    pub vs: Option<f64>,
+}
+
+/// `GET general/stats`
+#[derive(Debug, Clone, Deserialize)]
+pub struct GeneralStats {
+    pub usercount: u64,
+    pub rankedcount: u64,
+}
+
+/// `GET users/by/league`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Leaderboard {
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LeaderboardEntry {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub username: String,
+    pub role: String,
+    pub country: Option<String>,
+    pub league: LeaderboardLeague,
+    /// Cursor of this entry, pass it as `after` to get the next page.
+    pub p: Prisecter,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LeaderboardLeague {
+    pub gamesplayed: u64,
+    pub gameswon: u64,
+    pub rank: Ranks,
+    pub tr: f64,
+    pub glicko: f64,
+    pub rd: f64,
+    pub apm: f64,
+    pub pps: f64,
+    pub vs: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Prisecter {
+    pub pri: f64,
+    pub sec: f64,
+    pub ter: f64,
+}
+
+impl std::fmt::Display for Prisecter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.pri, self.sec, self.ter)
+    }
+}
+
+impl FromStr for Prisecter {
+    type Err = ();
+
+    /// Parses the `pri:sec:ter` form used by the `after` query.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split(':').map(|p| p.trim().parse::<f64>());
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(Ok(pri)), Some(Ok(sec)), Some(Ok(ter)), None) => Ok(Self { pri, sec, ter }),
+            _ => Err(()),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Ranks {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Ranks::from_str(&s).map_err(|_| serde::de::Error::custom(format!("unknown rank {s:?}")))
+    }
+}
+
+/// Serialized the way ch.tetr.io spells ranks (`x+`, `s-`, `z`...), so it round-trips.
+impl Serialize for Ranks {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.symbol().to_lowercase())
+    }
+}
+
+pub(crate) fn avatar_url(id: &str, revision: Option<u64>) -> String {
+    match revision {
+        Some(rv) => format!("https://tetr.io/user-content/avatars/{id}.jpg?rv={rv}"),
+        None => format!("https://tetr.io/user-content/avatars/{id}.jpg"),
+    }
+}
+
+#[cfg(all(test, feature = "test"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_league_summary() {
+        let r: Response<LeagueSummary> = serde_json::from_str(
+            r#"{"success":true,"data":{"gamesplayed":10,"gameswon":5,"glicko":2257.86,"rd":66.04,"tr":23684.48,"rank":"u","apm":66.09,"pps":2.07,"vs":135.65},"cache":{"status":"hit","cached_at":1,"cached_until":2}}"#,
+        )
+        .unwrap();
+        let data = r.data.unwrap();
+        assert_eq!(data.rank, Ranks::U);
+        assert_eq!(data.apm, Some(66.09));
+        assert_eq!(r.cache.unwrap().status, CacheStatus::Hit);
+    }
+
+    #[test]
+    fn test_parse_error_envelope() {
+        let r: Response<User> =
+            serde_json::from_str(r#"{"success":false,"error":{"msg":"No such user!"}}"#).unwrap();
+        assert!(!r.success);
+        assert_eq!(r.error.unwrap().message(), "No such user!");
+    }
+
+    #[test]
+    fn test_missing_field_is_error() {
+        let r = serde_json::from_str::<Response<User>>(
+            r#"{"success":true,"data":{"_id":"abc","role":"user"}}"#,
+        );
+        assert!(r.is_err());
+    }
+
+    #[test]
+    fn test_unknown_rank_is_error() {
+        let r =
+            serde_json::from_str::<LeagueSummary>(r#"{"gamesplayed":0,"gameswon":0,"rank":"q"}"#);
+        assert!(r.is_err());
+    }
+
+    #[test]
+    fn test_rank_round_trip() {
+        for rank in [Ranks::XPlus, Ranks::SMinus, Ranks::U, Ranks::ALL, Ranks::Z] {
+            let json = serde_json::to_string(&rank).unwrap();
+            assert_eq!(serde_json::from_str::<Ranks>(&json).unwrap(), rank);
+        }
+        assert_eq!(serde_json::to_string(&Ranks::APlus).unwrap(), "\"a+\"");
+    }
+
+    #[test]
+    fn test_prisecter_display() {
+        let p = Prisecter {
+            pri: 10000.0,
+            sec: 24999.5,
+            ter: 0.25,
+        };
+        assert_eq!(p.to_string(), "10000:24999.5:0.25");
+        assert_eq!(p.to_string().parse::<Prisecter>(), Ok(p));
+        assert!("1:2".parse::<Prisecter>().is_err());
+        assert!("1:2:x".parse::<Prisecter>().is_err());
+    }
+}
@@ -0,0 +1,154 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use tokio::{sync::Mutex, time::Instant};
+
+/// Who a request is for. Background requests wait while any interactive request is queued.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Priority {
+    /// Someone is waiting on the answer, like a command invocation.
+    Interactive,
+    /// Nobody is waiting, like the leaderboard crawler.
+    Background,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+    /// Set when ch.tetr.io tells us to back off, nobody gets a token before this.
+    paused_until: Option<Instant>,
+}
+
+/// Token bucket shared by every clone of a [`crate::TetrioClient`].
+#[derive(Debug, Clone)]
+pub(crate) struct RateLimiter {
+    bucket: Arc<Mutex<Bucket>>,
+    interactive_waiting: Arc<AtomicUsize>,
+    per_second: f64,
+    burst: f64,
+}
+
+impl RateLimiter {
+    pub fn new(per_second: f64, burst: u32) -> Self {
+        let burst = burst.max(1) as f64;
+        Self {
+            bucket: Arc::new(Mutex::new(Bucket {
+                tokens: burst,
+                last_refill: Instant::now(),
+                paused_until: None,
+            })),
+            interactive_waiting: Arc::new(AtomicUsize::new(0)),
+            per_second,
+            burst,
+        }
+    }
+
+    /// Waits until a request may be sent.
+    pub async fn acquire(&self, priority: Priority) {
+        let _guard = match priority {
+            Priority::Interactive => Some(WaitingGuard::new(&self.interactive_waiting)),
+            Priority::Background => None,
+        };
+        loop {
+            if priority == Priority::Background
+                && self.interactive_waiting.load(Ordering::Acquire) > 0
+            {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                continue;
+            }
+            let wait = {
+                let mut bucket = self.bucket.lock().await;
+                let now = Instant::now();
+                match bucket.paused_until {
+                    Some(until) if until > now => until - now,
+                    _ => {
+                        bucket.paused_until = None;
+                        let elapsed = (now - bucket.last_refill).as_secs_f64();
+                        bucket.tokens = (bucket.tokens + elapsed * self.per_second).min(self.burst);
+                        bucket.last_refill = now;
+                        if bucket.tokens >= 1.0 {
+                            bucket.tokens -= 1.0;
+                            return;
+                        }
+                        Duration::from_secs_f64((1.0 - bucket.tokens) / self.per_second)
+                    }
+                }
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Stops handing out tokens for `duration`, used when ch.tetr.io sends a `Retry-After`.
+    pub async fn pause_for(&self, duration: Duration) {
+        let mut bucket = self.bucket.lock().await;
+        let until = Instant::now() + duration;
+        if bucket.paused_until.is_none_or(|u| u < until) {
+            bucket.paused_until = Some(until);
+        }
+        bucket.tokens = 0.0;
+    }
+}
+
+struct WaitingGuard<'a>(&'a AtomicUsize);
+
+impl<'a> WaitingGuard<'a> {
+    fn new(counter: &'a AtomicUsize) -> Self {
+        counter.fetch_add(1, Ordering::AcqRel);
+        Self(counter)
+    }
+}
+
+impl Drop for WaitingGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Exponential backoff for the `attempt`th retry (starting at 0), jittered between half and the
+/// full delay so concurrent retries spread out.
+pub(crate) fn backoff(base: Duration, max: Duration, attempt: u32) -> Duration {
+    let ceiling = base
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(max)
+        .as_millis() as u64;
+    Duration::from_millis(fastrand::u64(ceiling / 2..=ceiling.max(1)))
+}
+
+#[cfg(all(test, feature = "test"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_is_capped() {
+        let max = Duration::from_secs(30);
+        for attempt in 0..20 {
+            assert!(backoff(Duration::from_millis(500), max, attempt) <= max);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_bucket_refills() {
+        let limiter = RateLimiter::new(2.0, 2);
+        let start = Instant::now();
+        for _ in 0..4 {
+            limiter.acquire(Priority::Interactive).await;
+        }
+        // 2 from the burst, 2 more at 2 per second
+        assert!(Instant::now() - start >= Duration::from_secs(1));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_pause() {
+        let limiter = RateLimiter::new(100.0, 10);
+        limiter.pause_for(Duration::from_secs(5)).await;
+        let start = Instant::now();
+        limiter.acquire(Priority::Background).await;
+        assert!(Instant::now() - start >= Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,107 @@
+use crate::{solver, Direction, ProfileStats};
+
+/// Relative step of the central difference. The playstyle metrics are rounded to 4 decimals, so
+/// much smaller steps would only measure the rounding.
+const RELATIVE_STEP: f64 = 0.02;
+
+/// The stats everything else is derived from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BaseStat {
+    Apm,
+    Pps,
+    Vs,
+}
+
+impl BaseStat {
+    pub const ALL: [BaseStat; 3] = [BaseStat::Apm, BaseStat::Pps, BaseStat::Vs];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BaseStat::Apm => "APM",
+            BaseStat::Pps => "PPS",
+            BaseStat::Vs => "VS",
+        }
+    }
+
+    pub fn of(&self, player: &ProfileStats) -> f64 {
+        match self {
+            BaseStat::Apm => player.apm as f64,
+            BaseStat::Pps => player.pps as f64,
+            BaseStat::Vs => player.vs as f64,
+        }
+    }
+
+    pub fn direction(&self) -> Direction {
+        match self {
+            BaseStat::Apm => Direction::APM,
+            BaseStat::Pps => Direction::PPS,
+            BaseStat::Vs => Direction::VS,
+        }
+    }
+}
+
+impl std::fmt::Display for BaseStat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// How much a metric moves when one base stat does, everything else held still.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Partial {
+    pub base: BaseStat,
+    /// Change of the metric per +1 of the base stat.
+    pub per_unit: f64,
+    /// Change of the metric for 1% more of the base stat, comparable across base stats.
+    pub per_percent: f64,
+}
+
+/// Partial derivatives of `metric` with respect to APM, PPS and VS at `player`, biggest gain
+/// per percent first.
+pub fn partials(player: &ProfileStats, metric: impl Fn(&ProfileStats) -> f64) -> Vec<Partial> {
+    let mut partials: Vec<Partial> = BaseStat::ALL
+        .iter()
+        .filter_map(|base| {
+            let value = base.of(player);
+            let h = value * RELATIVE_STEP;
+            let up = solver::apply(player, base.direction(), h);
+            let down = solver::apply(player, base.direction(), -h);
+            // the stats are f32, so measure the step that was actually taken
+            let taken = base.of(&up) - base.of(&down);
+            let per_unit = (metric(&up) - metric(&down)) / taken;
+            per_unit.is_finite().then_some(Partial {
+                base: *base,
+                per_unit,
+                per_percent: per_unit * value / 100.0,
+            })
+        })
+        .collect();
+    partials.sort_by(|a, b| b.per_percent.total_cmp(&a.per_percent));
+    partials
+}
+
+#[cfg(all(test, feature = "test"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_linear_metric() {
+        let player = ProfileStats::from_stat(60.0, 2.0, 120.0);
+        let p = partials(&player, |p| p.apm as f64 * 3.0 + p.vs as f64);
+        assert_eq!(p[0].base, BaseStat::Apm);
+        assert!((p[0].per_unit - 3.0).abs() < 1e-3);
+        assert!((p[0].per_percent - 1.8).abs() < 1e-3);
+        assert!((p[1].per_unit - 1.0).abs() < 1e-3);
+        assert!(p[2].per_unit.abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_estimated_tr_levers() {
+        let player = ProfileStats::from_stat(60.0, 2.0, 120.0);
+        let p = partials(&player, ProfileStats::estimated_tr);
+        let of = |base| p.iter().find(|p| p.base == base).unwrap().per_unit;
+        // matches what the solver relies on: VS helps, APM at the same VS hurts
+        assert!(of(BaseStat::Vs) > 0.0);
+        assert!(of(BaseStat::Apm) < 0.0);
+    }
+}
@@ -0,0 +1,170 @@
+use crate::ProfileStats;
+
+/// Highest APM, PPS and VS the solver will suggest, well past anyone on the leaderboard.
+const MAX_STATS: [f64; 3] = [400.0, 8.0, 800.0];
+/// Lowest a stat may drop to, the formulas divide by all three.
+const MIN_STAT: f64 = 0.01;
+/// Points sampled on each side before bisecting, `estimated_tr` isn't monotonic in PPS.
+const SAMPLES: usize = 400;
+const BISECT_STEPS: usize = 60;
+
+/// A change of APM, PPS and VS. The solver moves along it in multiples of itself.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Direction {
+    pub apm: f64,
+    pub pps: f64,
+    pub vs: f64,
+}
+
+impl Direction {
+    pub const APM: Direction = Direction {
+        apm: 1.0,
+        pps: 0.0,
+        vs: 0.0,
+    };
+    pub const PPS: Direction = Direction {
+        apm: 0.0,
+        pps: 1.0,
+        vs: 0.0,
+    };
+    pub const VS: Direction = Direction {
+        apm: 0.0,
+        pps: 0.0,
+        vs: 1.0,
+    };
+
+    /// All three stats scaled together, a step of `0.1` is 10% more of each.
+    pub fn proportional(player: &ProfileStats) -> Self {
+        Self {
+            apm: player.apm as f64,
+            pps: player.pps as f64,
+            vs: player.vs as f64,
+        }
+    }
+
+    fn components(&self) -> [f64; 3] {
+        [self.apm, self.pps, self.vs]
+    }
+}
+
+/// The smallest step along a [`Direction`] that reaches a target.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solution {
+    /// Multiples of the direction, negative when the stats have to go down.
+    pub step: f64,
+    /// The player after the step.
+    pub stats: ProfileStats,
+    pub estimated_tr: f64,
+}
+
+/// `player` moved `step` along `direction`.
+pub fn apply(player: &ProfileStats, direction: Direction, step: f64) -> ProfileStats {
+    ProfileStats {
+        apm: (player.apm as f64 + direction.apm * step) as f32,
+        pps: (player.pps as f64 + direction.pps * step) as f32,
+        vs: (player.vs as f64 + direction.vs * step) as f32,
+        ..player.clone()
+    }
+}
+
+/// Finds the smallest change along `direction` (either way) that gets `player`'s estimated TR
+/// to at least `target_tr`. `None` when no stats within reason get there.
+pub fn solve(player: &ProfileStats, direction: Direction, target_tr: f64) -> Option<Solution> {
+    let reaches = |step: f64| apply(player, direction, step).estimated_tr() >= target_tr;
+    if reaches(0.0) {
+        return Some(Solution {
+            step: 0.0,
+            stats: player.clone(),
+            estimated_tr: player.estimated_tr(),
+        });
+    }
+
+    let (lowest, highest) = step_range(player, direction)?;
+    [highest, lowest]
+        .into_iter()
+        .filter(|end| *end != 0.0)
+        .filter_map(|end| {
+            // scan out from the current stats, then bisect the first crossing
+            let mut inside = 0.0;
+            let mut outside = None;
+            for i in 1..=SAMPLES {
+                let step = end * i as f64 / SAMPLES as f64;
+                if reaches(step) {
+                    outside = Some(step);
+                    break;
+                }
+                inside = step;
+            }
+            let mut outside = outside?;
+            for _ in 0..BISECT_STEPS {
+                let mid = (inside + outside) / 2.0;
+                if reaches(mid) {
+                    outside = mid;
+                } else {
+                    inside = mid;
+                }
+            }
+            Some(outside)
+        })
+        .min_by(|a, b| a.abs().total_cmp(&b.abs()))
+        .map(|step| {
+            let stats = apply(player, direction, step);
+            Solution {
+                step,
+                estimated_tr: stats.estimated_tr(),
+                stats,
+            }
+        })
+}
+
+/// How far the stats can move along `direction` before one leaves `MIN_STAT..=MAX_STATS`.
+fn step_range(player: &ProfileStats, direction: Direction) -> Option<(f64, f64)> {
+    let values = [player.apm as f64, player.pps as f64, player.vs as f64];
+    let mut lowest = f64::NEG_INFINITY;
+    let mut highest = f64::INFINITY;
+    for ((value, d), max) in values.iter().zip(direction.components()).zip(MAX_STATS) {
+        if d == 0.0 {
+            continue;
+        }
+        let (a, b) = ((MIN_STAT - value) / d, (max - value) / d);
+        lowest = lowest.max(a.min(b));
+        highest = highest.min(a.max(b));
+    }
+    (lowest.is_finite() && highest.is_finite()).then_some((lowest.min(0.0), highest.max(0.0)))
+}
+
+#[cfg(all(test, feature = "test"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reaches_target() {
+        let player = ProfileStats::from_stat(60.0, 2.0, 120.0);
+        let target = player.estimated_tr() + 1000.0;
+        for direction in [
+            Direction::PPS,
+            Direction::VS,
+            Direction::proportional(&player),
+        ] {
+            let solution = solve(&player, direction, target).unwrap();
+            assert!(solution.estimated_tr >= target);
+            assert!((solution.estimated_tr - target).abs() < 1.0);
+            assert!(solution.step > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_apm_goes_down() {
+        // with VS fixed, more APM means less downstack, which the estimate punishes
+        let player = ProfileStats::from_stat(60.0, 2.0, 120.0);
+        let solution = solve(&player, Direction::APM, player.estimated_tr() + 100.0).unwrap();
+        assert!(solution.step < 0.0);
+    }
+
+    #[test]
+    fn test_already_there_and_unreachable() {
+        let player = ProfileStats::from_stat(60.0, 2.0, 120.0);
+        assert_eq!(solve(&player, Direction::VS, 0.0).unwrap().step, 0.0);
+        assert_eq!(solve(&player, Direction::PPS, 30000.0), None);
+    }
+}
@@ -0,0 +1,372 @@
+use std::sync::LazyLock;
+
+use crate::{weights, Metric, ProfileStats};
+
+/// Built from [`weights`] so the shown formula can't drift from [`ProfileStats::area`].
+static AREA_FORMULA: LazyLock<String> = LazyLock::new(|| {
+    format!(
+        "APM × {} + PPS × {} + VS × {} + APP × {} + DS/Second × {} + DS/Piece × {} \
+         + Garbage Efficiency × {}",
+        weights::APM_WEIGHT,
+        weights::PPS_WEIGHT,
+        weights::VS_WEIGHT,
+        weights::APP_WEIGHT,
+        weights::DS_SECONDS_WEIGHT,
+        weights::DS_PIECES_WEIGHT,
+        weights::GARBAGE_EFFICIENCY_WEIGHT
+    )
+});
+
+/// Every number osker shows about a player, so commands can loop over them instead of listing
+/// each one by hand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stat {
+    Apm,
+    Pps,
+    Vs,
+    DsPiece,
+    App,
+    AppDsPiece,
+    DsSecond,
+    VsApm,
+    GarbageEfficiency,
+    CheeseIndex,
+    WeightedApp,
+    Area,
+    /// The stat rank the playstyle metrics are measured against.
+    Sr,
+    Tr,
+    EstimatedTr,
+    EstimatedTrAccuracy,
+    Glicko,
+    Rd,
+    Opener,
+    Plonk,
+    Stride,
+    InfiniteDownstack,
+}
+
+impl Stat {
+    /// Every stat, grouped the way the `ts` embed shows them.
+    pub const ALL: &'static [Stat] = &[
+        Stat::Apm,
+        Stat::Pps,
+        Stat::Vs,
+        Stat::DsPiece,
+        Stat::App,
+        Stat::AppDsPiece,
+        Stat::DsSecond,
+        Stat::VsApm,
+        Stat::GarbageEfficiency,
+        Stat::CheeseIndex,
+        Stat::WeightedApp,
+        Stat::Area,
+        Stat::Sr,
+        Stat::Tr,
+        Stat::EstimatedTr,
+        Stat::EstimatedTrAccuracy,
+        Stat::Glicko,
+        Stat::Rd,
+        Stat::Opener,
+        Stat::Plonk,
+        Stat::Stride,
+        Stat::InfiniteDownstack,
+    ];
+
+    /// The radar chart's axes, in order.
+    const RADAR: &'static [Stat] = &[
+        Stat::Apm,
+        Stat::Pps,
+        Stat::Vs,
+        Stat::App,
+        Stat::DsSecond,
+        Stat::DsPiece,
+        Stat::AppDsPiece,
+        Stat::VsApm,
+        Stat::CheeseIndex,
+        Stat::GarbageEfficiency,
+    ];
+
+    /// Stats on the radar chart, in the order of its axes.
+    pub fn radar() -> impl Iterator<Item = Stat> {
+        Stat::RADAR.iter().copied()
+    }
+}
+
+impl Metric for Stat {
+    fn name(&self) -> &'static str {
+        match self {
+            Stat::Apm => "APM",
+            Stat::Pps => "PPS",
+            Stat::Vs => "VS",
+            Stat::DsPiece => "DS/Piece",
+            Stat::App => "APP",
+            Stat::AppDsPiece => "APP+DS/Piece",
+            Stat::DsSecond => "DS/Second",
+            Stat::VsApm => "VS/APM",
+            Stat::GarbageEfficiency => "Garbage Efficiency",
+            Stat::CheeseIndex => "Cheese Index",
+            Stat::WeightedApp => "Weighted APP",
+            Stat::Area => "Area",
+            Stat::Sr => "SR",
+            Stat::Tr => "TR",
+            Stat::EstimatedTr => "Estimated TR",
+            Stat::EstimatedTrAccuracy => "Estimated TR Accuracy",
+            Stat::Glicko => "Glicko",
+            Stat::Rd => "RD",
+            Stat::Opener => "Opener",
+            Stat::Plonk => "Plonk",
+            Stat::Stride => "Stride",
+            Stat::InfiniteDownstack => "Infinite Downstack",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Stat::DsPiece => "DS/P",
+            Stat::AppDsPiece => "APP+DS/P",
+            Stat::DsSecond => "DS/S",
+            Stat::GarbageEfficiency => "GE",
+            Stat::CheeseIndex => "CI",
+            Stat::WeightedApp => "wAPP",
+            Stat::EstimatedTr => "Est. TR",
+            Stat::EstimatedTrAccuracy => "Est. TR Acc.",
+            Stat::InfiniteDownstack => "Inf. DS",
+            _ => self.name(),
+        }
+    }
+
+    fn formula(&self) -> &'static str {
+        match self {
+            Stat::Apm | Stat::Pps | Stat::Vs => "base stat",
+            Stat::Tr | Stat::Glicko | Stat::Rd => "from TETRA LEAGUE",
+            Stat::DsPiece => "DS/Second / PPS",
+            Stat::App => "APM / (PPS × 60)",
+            Stat::AppDsPiece => "APP + DS/Piece",
+            Stat::DsSecond => "VS / 100 − APM / 60",
+            Stat::VsApm => "VS / APM",
+            Stat::GarbageEfficiency => "APP × DS/Second / PPS × 2",
+            Stat::CheeseIndex => "DS/Piece × 150 + (VS/APM − 2) × 50 + (0.6 − APP) × 125",
+            Stat::WeightedApp => "APP − 5 × tan((Cheese Index / −30 + 1)°)",
+            Stat::Area => &AREA_FORMULA,
+            Stat::Sr => "max(11.2 × atan((SR Area − 93) / 130) + 1, 0.001)",
+            Stat::EstimatedTr => {
+                "25000 / (1 + 10^((1500 − Estimated Glicko) × π / \
+                 √(3 × ln(10)² × 60² + 2500 × (64 × π² + 147 × ln(10)²))))"
+            }
+            Stat::EstimatedTrAccuracy => "Estimated TR − TR",
+            Stat::Opener => {
+                "(APM term + PPS term × 0.75 − VS/APM term × 10 + APP term × 0.75 − DS/Piece term \
+                 × 0.25) / 3.5 + 0.5"
+            }
+            Stat::Plonk => {
+                "(Garbage Efficiency term + APP term + DS/Piece term × 0.75 − PPS term) / 2.73 + \
+                 0.5, rounded to 4 decimals"
+            }
+            Stat::Stride => {
+                "(PPS term − APM term × 0.25 − APP term × 2 − DS/Piece term × 0.5) × 0.79 + 0.5, \
+                 rounded to 4 decimals"
+            }
+            Stat::InfiniteDownstack => {
+                "(DS/Piece term − APP term × 0.75 + APM term × 0.5 + VS/APM term × 1.5 + PPS term \
+                 × 0.5) × 0.9 + 0.5, rounded to 4 decimals"
+            }
+        }
+    }
+
+    fn unit(&self) -> &'static str {
+        match self {
+            Stat::Apm => "attack/min",
+            Stat::Pps => "pieces/s",
+            Stat::DsPiece => "lines/piece",
+            Stat::App | Stat::WeightedApp => "attack/piece",
+            Stat::DsSecond => "lines/s",
+            Stat::Tr | Stat::EstimatedTr | Stat::EstimatedTrAccuracy => "TR",
+            _ => "",
+        }
+    }
+
+    fn precision(&self) -> usize {
+        match self {
+            Stat::Apm
+            | Stat::Pps
+            | Stat::Vs
+            | Stat::Tr
+            | Stat::EstimatedTr
+            | Stat::EstimatedTrAccuracy
+            | Stat::Glicko
+            | Stat::Rd => 2,
+            _ => 4,
+        }
+    }
+
+    fn radar_label(&self) -> &'static str {
+        match self {
+            Stat::DsSecond => "DS/Seconds",
+            Stat::DsPiece => "DS/Pieces",
+            _ => self.name(),
+        }
+    }
+
+    fn radar_weight(&self) -> Option<f64> {
+        Some(match self {
+            Stat::Apm => weights::APM_WEIGHT as f64,
+            Stat::Pps => weights::PPS_WEIGHT as f64,
+            Stat::Vs => weights::VS_WEIGHT,
+            Stat::App => weights::APP_WEIGHT as f64,
+            Stat::DsSecond => weights::DS_SECONDS_WEIGHT as f64,
+            Stat::DsPiece => weights::DS_PIECES_WEIGHT as f64,
+            Stat::AppDsPiece => weights::DS_APP_WEIGHT as f64,
+            Stat::VsApm => weights::VS_APM_WEIGHT as f64,
+            Stat::CheeseIndex => weights::CHEESE_INDEX_WEIGHT,
+            Stat::GarbageEfficiency => weights::GARBAGE_EFFICIENCY_WEIGHT as f64,
+            _ => return None,
+        })
+    }
+
+    fn higher_is_better(&self) -> Option<bool> {
+        match self {
+            Stat::Rd => Some(false),
+            Stat::CheeseIndex
+            | Stat::EstimatedTrAccuracy
+            | Stat::Opener
+            | Stat::Plonk
+            | Stat::Stride
+            | Stat::InfiniteDownstack => None,
+            _ => Some(true),
+        }
+    }
+
+    /// `None` for league stats of made up players.
+    fn of(&self, player: &ProfileStats) -> Option<f64> {
+        Some(match self {
+            Stat::Apm => player.apm as f64,
+            Stat::Pps => player.pps as f64,
+            Stat::Vs => player.vs as f64,
+            Stat::DsPiece => player.ds_pieces(),
+            Stat::App => player.app(),
+            Stat::AppDsPiece => player.app_ds_per_pieces(),
+            Stat::DsSecond => player.ds_seconds(),
+            Stat::VsApm => player.vs_apm(),
+            Stat::GarbageEfficiency => player.garbage_efficiency(),
+            Stat::CheeseIndex => player.cheese_index(),
+            Stat::WeightedApp => player.weighted_app(),
+            Stat::Area => player.area(),
+            Stat::Sr => player.sr(),
+            Stat::Tr => player.tr.filter(|_| player.is_real)?,
+            Stat::EstimatedTr => player.estimated_tr(),
+            Stat::EstimatedTrAccuracy => {
+                player.tr.filter(|_| player.is_real)?;
+                player.accuracy_tr()
+            }
+            Stat::Glicko => player.glicko.filter(|_| player.is_real)?,
+            Stat::Rd => player.rd.filter(|_| player.is_real)?,
+            Stat::Opener => player.opener(),
+            Stat::Plonk => player.plonk(),
+            Stat::Stride => player.stride(),
+            Stat::InfiniteDownstack => player.infinite_downstack(),
+        })
+    }
+}
+
+impl std::str::FromStr for Stat {
+    type Err = ();
+
+    /// Accepts display names and method names alike, ignoring case and punctuation, so
+    /// `DS/Piece`, `ds_pieces` and `dspiece` all work.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalize = |s: &str| {
+            s.chars()
+                .filter(char::is_ascii_alphanumeric)
+                .collect::<String>()
+                .to_lowercase()
+        };
+        let s = normalize(s);
+        let alias = match s.as_str() {
+            "dspieces" => Some(Stat::DsPiece),
+            "appdsperpieces" => Some(Stat::AppDsPiece),
+            "dsseconds" => Some(Stat::DsSecond),
+            "ge" => Some(Stat::GarbageEfficiency),
+            "ci" => Some(Stat::CheeseIndex),
+            "accuracytr" => Some(Stat::EstimatedTrAccuracy),
+            "inf" | "infds" => Some(Stat::InfiniteDownstack),
+            _ => None,
+        };
+        alias
+            .or_else(|| {
+                Stat::ALL
+                    .iter()
+                    .copied()
+                    .find(|stat| normalize(stat.name()) == s)
+            })
+            .ok_or(())
+    }
+}
+
+impl std::fmt::Display for Stat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(all(test, feature = "test"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_made_up_players_have_no_league_stats() {
+        let player = ProfileStats::from_stat(60.0, 2.0, 130.0);
+        assert_eq!(Stat::Apm.of(&player), Some(60.0));
+        assert!(Stat::EstimatedTr.of(&player).is_some());
+        assert_eq!(Stat::Tr.of(&player), None);
+        assert_eq!(Stat::EstimatedTrAccuracy.of(&player), None);
+        assert_eq!(Stat::Rd.of(&player), None);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("APP".parse(), Ok(Stat::App));
+        assert_eq!("ds_pieces".parse(), Ok(Stat::DsPiece));
+        assert_eq!("DS/Piece".parse(), Ok(Stat::DsPiece));
+        assert_eq!("cheese_index".parse(), Ok(Stat::CheeseIndex));
+        assert_eq!("estimated_tr".parse(), Ok(Stat::EstimatedTr));
+        assert_eq!("nope".parse::<Stat>(), Err(()));
+        for stat in Stat::ALL {
+            assert_eq!(stat.name().parse(), Ok(*stat));
+        }
+    }
+
+    #[test]
+    fn test_all_is_complete() {
+        let mut names: Vec<&str> = Stat::ALL.iter().map(Stat::name).collect();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), Stat::ALL.len());
+    }
+
+    #[test]
+    fn test_radar() {
+        let player = ProfileStats::from_stat(60.0, 2.0, 130.0);
+        let radar: Vec<Stat> = Stat::radar().collect();
+        assert_eq!(radar.len(), 10);
+        for stat in Stat::ALL {
+            assert_eq!(radar.contains(stat), stat.radar_weight().is_some());
+        }
+        assert_eq!(Stat::DsSecond.radar_label(), "DS/Seconds");
+        assert_eq!(Stat::Vs.radar_value(&player), Some(130.0 * 0.444));
+        assert_eq!(Stat::Opener.radar_value(&player), None);
+    }
+
+    #[test]
+    fn test_labels_and_display() {
+        let mut labels: Vec<&str> = Stat::ALL.iter().map(Stat::label).collect();
+        labels.sort_unstable();
+        labels.dedup();
+        assert_eq!(labels.len(), Stat::ALL.len());
+        assert_eq!(Stat::Apm.display(60.0), "60.00 attack/min");
+        assert_eq!(Stat::Opener.display(0.5), "0.5000");
+        assert!(Stat::Area
+            .formula()
+            .starts_with("APM × 1 + PPS × 45 + VS × 0.444"));
+    }
+}